@@ -0,0 +1,8 @@
+extern crate chess;
+
+use chess::protocol::uci::Uci;
+
+fn main() {
+    let stdin = std::io::stdin();
+    Uci::new(std::io::stdout()).run(stdin.lock()).unwrap();
+}
@@ -0,0 +1,8 @@
+extern crate chess;
+
+use chess::protocol::xboard::XBoard;
+
+fn main() {
+    let stdin = std::io::stdin();
+    XBoard::new(std::io::stdout()).run(stdin.lock()).unwrap();
+}
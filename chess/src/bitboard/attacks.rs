@@ -0,0 +1,115 @@
+use super::magic::{bishop_attacks, rook_attacks};
+use super::{color_index, Bitboard};
+use crate::{Color, Pos};
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+const WHITE_PAWN_OFFSETS: [(i8, i8); 2] = [(1, 1), (-1, 1)];
+const BLACK_PAWN_OFFSETS: [(i8, i8); 2] = [(1, -1), (-1, -1)];
+
+pub const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+pub const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+const fn leaper_attacks(offsets: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut table = [Bitboard::EMPTY; 64];
+
+    let mut i = 0;
+    while i < 64 {
+        let x = (i % 8) as i8;
+        let y = (i / 8) as i8;
+
+        let mut bits = 0;
+        let mut j = 0;
+        while j < offsets.len() {
+            let (to_x, to_y) = (x + offsets[j].0, y + offsets[j].1);
+            if to_x >= 0 && to_x < 8 && to_y >= 0 && to_y < 8 {
+                bits |= 1 << (to_y * 8 + to_x);
+            }
+            j += 1;
+        }
+
+        table[i] = Bitboard(bits);
+        i += 1;
+    }
+
+    table
+}
+
+static KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+static KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_OFFSETS);
+static PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&WHITE_PAWN_OFFSETS),
+    leaper_attacks(&BLACK_PAWN_OFFSETS),
+];
+
+pub fn knight_attacks(pos: Pos) -> Bitboard {
+    KNIGHT_ATTACKS[pos.index()]
+}
+
+pub fn king_attacks(pos: Pos) -> Bitboard {
+    KING_ATTACKS[pos.index()]
+}
+
+// Squares a pawn of the given color on pos attacks
+pub fn pawn_attacks(pos: Pos, color: Color) -> Bitboard {
+    PAWN_ATTACKS[color_index(color)][pos.index()]
+}
+
+// Walks each direction until the edge or the first occupied square, which is included.
+// Slow, only used to build and verify the magic tables
+pub fn ray_attacks(pos: Pos, occupied: Bitboard, directions: &[(i8, i8)]) -> Bitboard {
+    let mut attacks = Bitboard::EMPTY;
+
+    for &(x, y) in directions.iter() {
+        let mut current = pos;
+        while let Some(to) = current.move_xy(x, y) {
+            attacks.insert(to);
+            if occupied.contains(to) {
+                break;
+            }
+            current = to;
+        }
+    }
+
+    attacks
+}
+
+// Squares strictly between two squares sharing a rank, file or diagonal
+pub fn between(a: Pos, b: Pos) -> Bitboard {
+    let occupied = Bitboard::from_pos(a) | Bitboard::from_pos(b);
+    let (dist_x, dist_y) = (a.distance_x(&b), a.distance_y(&b));
+
+    if a == b {
+        Bitboard::EMPTY
+    } else if dist_x == 0 || dist_y == 0 {
+        rook_attacks(a, occupied) & rook_attacks(b, occupied)
+    } else if dist_x == dist_y {
+        bishop_attacks(a, occupied) & bishop_attacks(b, occupied)
+    } else {
+        Bitboard::EMPTY
+    }
+}
+
+pub fn queen_attacks(pos: Pos, occupied: Bitboard) -> Bitboard {
+    rook_attacks(pos, occupied) | bishop_attacks(pos, occupied)
+}
@@ -0,0 +1,151 @@
+use crate::{Color, Pos};
+
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+pub mod attacks;
+mod magic;
+
+pub use attacks::*;
+pub use magic::{bishop_attacks, rook_attacks};
+
+#[cfg(test)]
+mod tests;
+
+// Set of squares, bit i is the square with Pos index i
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Self = Self(0);
+    pub const FULL: Self = Self(u64::MAX);
+
+    pub const fn from_index(i: u8) -> Self {
+        Self(1 << i)
+    }
+
+    pub fn from_pos(pos: Pos) -> Self {
+        Self(1 << pos.index())
+    }
+
+    pub fn rank(y: u8) -> Self {
+        Self(0xFF << (y * 8))
+    }
+
+    pub fn file(x: u8) -> Self {
+        Self(0x0101_0101_0101_0101 << x)
+    }
+
+    pub fn contains(self, pos: Pos) -> bool {
+        self.0 & (1 << pos.index()) != 0
+    }
+
+    pub fn insert(&mut self, pos: Pos) {
+        self.0 |= 1 << pos.index();
+    }
+
+    pub fn remove(&mut self, pos: Pos) {
+        self.0 &= !(1 << pos.index());
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn first(self) -> Option<Pos> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Pos::new_index(self.0.trailing_zeros() as u8))
+        }
+    }
+}
+
+impl Iterator for Bitboard {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        let pos = self.first()?;
+        self.0 &= self.0 - 1;
+        Some(pos)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.count() as usize;
+        (count, Some(count))
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitXorAssign for Bitboard {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
+}
+
+impl std::fmt::Debug for Bitboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in (0..8).rev() {
+            for x in 0..8 {
+                let c = if self.contains(Pos::new_xy(x, y)) {
+                    'x'
+                } else {
+                    '.'
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn color_index(color: Color) -> usize {
+    (color as usize) >> 7
+}
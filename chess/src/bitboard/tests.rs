@@ -0,0 +1,105 @@
+use super::*;
+
+use std::str::FromStr;
+
+fn squares(bitboard: Bitboard) -> Vec<String> {
+    bitboard.map(|pos| pos.to_string()).collect()
+}
+
+#[test]
+fn set_operations() {
+    let mut bitboard = Bitboard::EMPTY;
+    assert!(bitboard.is_empty());
+
+    bitboard.insert(Pos::from_str("e4").unwrap());
+    bitboard.insert(Pos::from_str("a1").unwrap());
+    bitboard.insert(Pos::from_str("h8").unwrap());
+    assert_eq!(bitboard.count(), 3);
+    assert!(bitboard.contains(Pos::from_str("e4").unwrap()));
+    assert_eq!(bitboard.first(), Some(Pos::from_str("a1").unwrap()));
+    assert_eq!(squares(bitboard), ["a1", "e4", "h8"]);
+
+    bitboard.remove(Pos::from_str("e4").unwrap());
+    assert_eq!(squares(bitboard), ["a1", "h8"]);
+
+    assert_eq!(
+        Bitboard::rank(0) & Bitboard::file(0),
+        Bitboard::from_index(0)
+    );
+    assert_eq!((Bitboard::rank(3) | !Bitboard::rank(3)), Bitboard::FULL);
+}
+
+#[test]
+fn leaper_attacks() {
+    let pos = |s| Pos::from_str(s).unwrap();
+
+    assert_eq!(squares(knight_attacks(pos("a1"))), ["c2", "b3"]);
+    assert_eq!(knight_attacks(pos("e4")).count(), 8);
+    assert_eq!(knight_attacks(pos("h5")).count(), 4);
+
+    assert_eq!(squares(king_attacks(pos("a1"))), ["b1", "a2", "b2"]);
+    assert_eq!(king_attacks(pos("e4")).count(), 8);
+
+    assert_eq!(squares(pawn_attacks(pos("e4"), Color::White)), ["d5", "f5"]);
+    assert_eq!(squares(pawn_attacks(pos("e4"), Color::Black)), ["d3", "f3"]);
+    assert_eq!(squares(pawn_attacks(pos("a2"), Color::White)), ["b3"]);
+    assert!(pawn_attacks(pos("h8"), Color::White).is_empty());
+}
+
+#[test]
+fn slider_attacks() {
+    let pos = |s| Pos::from_str(s).unwrap();
+
+    assert_eq!(rook_attacks(pos("a1"), Bitboard::EMPTY).count(), 14);
+    assert_eq!(bishop_attacks(pos("d4"), Bitboard::EMPTY).count(), 13);
+    assert_eq!(queen_attacks(pos("d4"), Bitboard::EMPTY).count(), 27);
+
+    let mut occupied = Bitboard::EMPTY;
+    occupied.insert(pos("a3"));
+    occupied.insert(pos("c1"));
+    assert_eq!(
+        squares(rook_attacks(pos("a1"), occupied)),
+        ["b1", "c1", "a2", "a3"]
+    );
+
+    occupied.insert(pos("b2"));
+    assert_eq!(squares(bishop_attacks(pos("a1"), occupied)), ["b2"]);
+}
+
+#[test]
+fn slider_tables_match_ray_walking() {
+    // xorshift64 with a fixed seed, sparse and dense occupancies both get covered
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for round in 0..2000 {
+        let occupied = match round % 3 {
+            0 => Bitboard(random() & random() & random()),
+            1 => Bitboard(random() & random()),
+            _ => Bitboard(random()),
+        };
+
+        for i in 0..64 {
+            let pos = Pos::new_index(i);
+            assert_eq!(
+                rook_attacks(pos, occupied),
+                ray_attacks(pos, occupied, &ROOK_DIRECTIONS),
+                "rook on {} with\n{:?}",
+                pos,
+                occupied
+            );
+            assert_eq!(
+                bishop_attacks(pos, occupied),
+                ray_attacks(pos, occupied, &BISHOP_DIRECTIONS),
+                "bishop on {} with\n{:?}",
+                pos,
+                occupied
+            );
+        }
+    }
+}
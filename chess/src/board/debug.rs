@@ -5,7 +5,7 @@ impl std::fmt::Debug for Board {
         for y in 0..8 {
             let start = y * 8;
             for x in 0..8 {
-                write!(f, "{}", self.0[start + x])?;
+                write!(f, "{}", self.squares[start + x])?;
             }
             writeln!(f)?;
         }
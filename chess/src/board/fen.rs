@@ -0,0 +1,246 @@
+use super::{Board, TaggedPiece};
+use crate::bitboard::Bitboard;
+use crate::{Color, PieceType, Pos};
+
+#[derive(Debug, PartialEq)]
+pub enum ParseFenError {
+    MissingField,
+    InvalidPlacement,
+    InvalidColor,
+    InvalidCastling,
+    InvalidEnPassant,
+    InvalidClock,
+    InvalidKings,
+}
+
+impl Board {
+    /// Parses the piece placement field of a FEN string. If the string also
+    /// contains a castling field it is applied as original markers on the
+    /// kings and rooks, all other fields are ignored.
+    pub fn from_fen(fen: &str) -> Result<Self, ParseFenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or(ParseFenError::MissingField)?;
+
+        let mut board = Self::empty();
+
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(ParseFenError::InvalidPlacement);
+        }
+
+        for (i, rank) in ranks.iter().enumerate() {
+            let y = 7 - i as u8;
+            let mut x: u8 = 0;
+            for c in rank.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    if skip == 0 || skip > 8 {
+                        return Err(ParseFenError::InvalidPlacement);
+                    }
+                    x += skip as u8;
+                } else {
+                    let r#type = PieceType::from_char(c.to_ascii_uppercase())
+                        .ok_or(ParseFenError::InvalidPlacement)?;
+                    let color = if c.is_ascii_uppercase() {
+                        Color::White
+                    } else {
+                        Color::Black
+                    };
+
+                    if x > 7 {
+                        return Err(ParseFenError::InvalidPlacement);
+                    }
+
+                    // A pawn on the first or last rank could never have got there
+                    if r#type == PieceType::Pawn && (y == 0 || y == 7) {
+                        return Err(ParseFenError::InvalidPlacement);
+                    }
+
+                    let pos = Pos::new_xy(x, y);
+                    if r#type == PieceType::Pawn && pos.at_pawn_rank(color) {
+                        board.set_pos(pos, TaggedPiece::original(r#type, color));
+                    } else {
+                        board.set_pos(pos, TaggedPiece::new(r#type, color));
+                    }
+                    x += 1;
+                }
+
+                if x > 8 {
+                    return Err(ParseFenError::InvalidPlacement);
+                }
+            }
+
+            if x != 8 {
+                return Err(ParseFenError::InvalidPlacement);
+            }
+        }
+
+        if let Some(castling) = fields.nth(1) {
+            board.apply_castling_fen(castling)?;
+        }
+
+        Ok(board)
+    }
+
+    // K and Q stand for the outermost rook on that side of the king as in
+    // X-FEN, a file letter names the rook as in Shredder-FEN
+    fn apply_castling_fen(&mut self, castling: &str) -> Result<(), ParseFenError> {
+        if castling == "-" {
+            return Ok(());
+        }
+
+        for c in castling.chars() {
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            let y = back_rank(color);
+            let king = self
+                .find_first_of_type(PieceType::King, color)
+                .filter(|king| king.y() == y)
+                .ok_or(ParseFenError::InvalidCastling)?;
+            let mut rooks = self.pieces_of(PieceType::Rook, color) & Bitboard::rank(y);
+
+            let rook = match c.to_ascii_uppercase() {
+                'K' => rooks.filter(|rook| rook.x() > king.x()).last(),
+                'Q' => rooks.find(|rook| rook.x() < king.x()),
+                file @ 'A'..='H' => {
+                    Some(Pos::new_xy(file as u8 - b'A', y)).filter(|&rook| rooks.contains(rook))
+                }
+                _ => None,
+            }
+            .ok_or(ParseFenError::InvalidCastling)?;
+
+            self.set_pos(king, TaggedPiece::original(PieceType::King, color));
+            self.set_pos(rook, TaggedPiece::original(PieceType::Rook, color));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the piece placement field of the FEN for this board.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::with_capacity(72);
+        for y in (0..8).rev() {
+            let mut empty = 0;
+            for x in 0..8 {
+                let piece = self.at_xy(x, y);
+                if piece.is_empty() {
+                    empty += 1;
+                    continue;
+                }
+
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                fen.push_str(&piece.to_string());
+            }
+
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+
+            if y > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen
+    }
+
+    /// Returns the castling field of the FEN for this board in X-FEN, derived
+    /// from the original markers on the kings and rooks. Standard positions get
+    /// the usual KQkq, file letters are only used for a rook with another one
+    /// further out on the same side.
+    pub fn castling_fen(&self) -> String {
+        self.format_castling(false)
+    }
+
+    /// Returns the castling field in Shredder-FEN, with every castling rook
+    /// named by its file.
+    pub fn shredder_castling_fen(&self) -> String {
+        self.format_castling(true)
+    }
+
+    fn format_castling(&self, shredder: bool) -> String {
+        let mut fen = String::with_capacity(4);
+        for &color in [Color::White, Color::Black].iter() {
+            for &king_side in [true, false].iter() {
+                let rook = match self.castling_rook(color, king_side) {
+                    Some(rook) => rook,
+                    None => continue,
+                };
+
+                let rooks = self.pieces_of(PieceType::Rook, color) & Bitboard::rank(rook.y());
+                let outermost = rooks
+                    .filter(|other| (other.x() > rook.x()) == king_side)
+                    .all(|other| other == rook);
+
+                let c = match (shredder || !outermost, king_side) {
+                    (true, _) => (b'a' + rook.x()) as char,
+                    (false, true) => 'k',
+                    (false, false) => 'q',
+                };
+
+                if color == Color::White {
+                    fen.push(c.to_ascii_uppercase());
+                } else {
+                    fen.push(c);
+                }
+            }
+        }
+
+        if fen.is_empty() {
+            fen.push('-');
+        }
+
+        fen
+    }
+
+    // The unmoved rook the king can castle with on one side, the outermost if
+    // there are several. Needs the king unmoved on its back rank as well.
+    pub fn castling_rook(&self, color: Color, king_side: bool) -> Option<Pos> {
+        let y = back_rank(color);
+        let king = self.find_first_of_type(PieceType::King, color)?;
+        if king.y() != y || !self.at_pos(king).is_original() {
+            return None;
+        }
+
+        let mut rooks = (self.pieces_of(PieceType::Rook, color) & Bitboard::rank(y))
+            .filter(|&rook| self.at_pos(rook).is_original())
+            .filter(|rook| (rook.x() > king.x()) == king_side);
+
+        if king_side {
+            rooks.last()
+        } else {
+            rooks.next()
+        }
+    }
+
+    pub fn has_castling_rights(&self, color: Color) -> bool {
+        self.castling_rook(color, true).is_some() || self.castling_rook(color, false).is_some()
+    }
+
+    // Castling rights standard chess can't have, with the king off the e-file
+    // or a castling rook off the corners
+    pub fn has_chess960_castling(&self) -> bool {
+        [Color::White, Color::Black].iter().any(|&color| {
+            [(true, 7), (false, 0)].iter().any(|&(king_side, corner)| {
+                match self.castling_rook(color, king_side) {
+                    Some(rook) => rook.x() != corner || self.find_king(color).x() != 4,
+                    None => false,
+                }
+            })
+        })
+    }
+}
+
+fn back_rank(color: Color) -> u8 {
+    if color == Color::White {
+        0
+    } else {
+        7
+    }
+}
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use super::{Board, TaggedPiece};
+use crate::Pos;
 
 #[derive(Debug)]
 pub enum ParseBoardError {
@@ -14,9 +15,7 @@ impl FromStr for Board {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let pieces = s.split_whitespace();
 
-        let mut board = Self {
-            0: [TaggedPiece::empty(); 64],
-        };
+        let mut board = Self::empty();
 
         for (i, p) in pieces.enumerate() {
             if i > 63 {
@@ -24,7 +23,7 @@ impl FromStr for Board {
             }
 
             if let Ok(piece) = TaggedPiece::from_str(p) {
-                board.0[i] = piece;
+                board.set_pos(Pos::new_index(i as u8), piece);
             } else {
                 return Err(ParseBoardError::InvalidPiece);
             }
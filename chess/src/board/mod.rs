@@ -1,20 +1,32 @@
+use crate::bitboard::{
+    bishop_attacks, color_index, king_attacks, knight_attacks, pawn_attacks, rook_attacks, Bitboard,
+};
 use crate::{Color, Move, PieceType, Pos, TaggedPiece};
 
 pub mod debug;
+pub mod fen;
 pub mod from_str;
+pub mod see;
+pub mod zobrist;
 
+pub use fen::*;
 pub use from_str::*;
 
 #[cfg(test)]
 mod tests;
 
+// Mailbox for square lookups with bitboards kept in sync for set queries
 #[derive(Copy, Clone)]
-pub struct Board([TaggedPiece; 64]);
+pub struct Board {
+    squares: [TaggedPiece; 64],
+    pieces: [Bitboard; 6],
+    colors: [Bitboard; 2],
+}
 
 impl PartialEq for Board {
     fn eq(&self, other: &Board) -> bool {
         for i in 0..64 {
-            if self.0[i] != other.0[i] {
+            if self.squares[i] != other.squares[i] {
                 return false;
             }
         }
@@ -23,44 +35,142 @@ impl PartialEq for Board {
     }
 }
 
+impl Eq for Board {}
+
+// Pieces make_move removes from their squares, enough to take the move back
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MoveUndo {
+    pub moved: TaggedPiece,
+    pub captured: TaggedPiece,
+    // Where the rook started when castling
+    pub rook_from: Option<Pos>,
+}
+
+// King and rook squares after castling, the same for every start position
+pub fn castling_targets(king: Pos, r#move: Move) -> (Pos, Pos) {
+    let y = king.y();
+    if r#move == Move::KingSideCastling {
+        (Pos::new_xy(6, y), Pos::new_xy(5, y))
+    } else {
+        (Pos::new_xy(2, y), Pos::new_xy(3, y))
+    }
+}
+
+// Back rank of the standard game, also Chess960 position 518
+const STANDARD_BACK_RANK: [PieceType; 8] = [
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Queen,
+    PieceType::King,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Rook,
+];
+
+// Squares the two knights take among the five left after the bishops and queen
+const CHESS960_KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
 impl Default for Board {
     fn default() -> Self {
-        let mut board = Self {
-            0: [TaggedPiece::empty(); 64],
-        };
+        Self::with_back_rank(STANDARD_BACK_RANK)
+    }
+}
+
+impl Board {
+    pub fn empty() -> Self {
+        Self {
+            squares: [TaggedPiece::empty(); 64],
+            pieces: [Bitboard::EMPTY; 6],
+            colors: [Bitboard::EMPTY; 2],
+        }
+    }
 
-        for i in 0..8 {
-            board.0[8 + i] = TaggedPiece::original(PieceType::Pawn, Color::White);
-            board.0[8 * 6 + i] = TaggedPiece::original(PieceType::Pawn, Color::Black);
+    // Pawns on the second and seventh ranks with the given pieces mirrored behind them
+    fn with_back_rank(back_rank: [PieceType; 8]) -> Self {
+        let mut board = Self::empty();
+
+        for (x, &r#type) in back_rank.iter().enumerate() {
+            let x = x as u8;
+            for &(color, pawn_y, piece_y) in [(Color::White, 1, 0), (Color::Black, 6, 7)].iter() {
+                board.set_pos(
+                    Pos::new_xy(x, pawn_y),
+                    TaggedPiece::original(PieceType::Pawn, color),
+                );
+                board.set_pos(
+                    Pos::new_xy(x, piece_y),
+                    TaggedPiece::original(r#type, color),
+                );
+            }
         }
 
-        let mut place_at_both_sides = |offset, r#type| {
-            board.0[offset] = TaggedPiece::original(r#type, Color::White);
-            board.0[8 * 7 + offset] = TaggedPiece::original(r#type, Color::Black);
-        };
+        board
+    }
 
-        let mut place_matching_at_both_sides = |offset, r#type| {
-            place_at_both_sides(offset, r#type);
-            place_at_both_sides(7 - offset, r#type);
+    // Chess960 start position by its standard number from 0 to 959, 518 is the
+    // regular setup. The bishops go first, then the queen and the knights on
+    // the squares left, and the king between the two rooks on the last three.
+    pub fn chess960(index: u16) -> Option<Self> {
+        if index >= 960 {
+            return None;
+        }
+
+        let mut back_rank: [Option<PieceType>; 8] = [None; 8];
+        let mut index = index as usize;
+
+        back_rank[(index % 4) * 2 + 1] = Some(PieceType::Bishop);
+        index /= 4;
+        back_rank[(index % 4) * 2] = Some(PieceType::Bishop);
+        index /= 4;
+
+        let empty = |back_rank: &[Option<PieceType>; 8]| -> Vec<usize> {
+            (0..8).filter(|&x| back_rank[x].is_none()).collect()
         };
 
-        place_matching_at_both_sides(0, PieceType::Rook);
-        place_matching_at_both_sides(1, PieceType::Knight);
-        place_matching_at_both_sides(2, PieceType::Bishop);
-        place_at_both_sides(3, PieceType::Queen);
-        place_at_both_sides(4, PieceType::King);
+        back_rank[empty(&back_rank)[index % 6]] = Some(PieceType::Queen);
+        index /= 6;
 
-        board
+        let (first, second) = CHESS960_KNIGHTS[index];
+        let squares = empty(&back_rank);
+        back_rank[squares[first]] = Some(PieceType::Knight);
+        back_rank[squares[second]] = Some(PieceType::Knight);
+
+        let squares = empty(&back_rank);
+        back_rank[squares[0]] = Some(PieceType::Rook);
+        back_rank[squares[1]] = Some(PieceType::King);
+        back_rank[squares[2]] = Some(PieceType::Rook);
+
+        Some(Self::with_back_rank(back_rank.map(Option::unwrap)))
     }
-}
 
-impl Board {
     pub fn set_pos(&mut self, pos: Pos, piece: TaggedPiece) {
-        self.0[pos.index()] = piece;
+        let old = self.squares[pos.index()];
+        if !old.is_empty() {
+            self.pieces[old.get_type() as usize - 1].remove(pos);
+            self.colors[color_index(old.color())].remove(pos);
+        }
+
+        if !piece.is_empty() {
+            self.pieces[piece.get_type() as usize - 1].insert(pos);
+            self.colors[color_index(piece.color())].insert(pos);
+        }
+
+        self.squares[pos.index()] = piece;
     }
 
     pub fn at_pos(&self, pos: Pos) -> TaggedPiece {
-        self.0[pos.index()]
+        self.squares[pos.index()]
     }
 
     pub fn at_xy(&self, x: u8, y: u8) -> TaggedPiece {
@@ -68,7 +178,23 @@ impl Board {
     }
 
     pub fn at_index(&self, i: usize) -> TaggedPiece {
-        self.0[i]
+        self.squares[i]
+    }
+
+    pub fn pieces(&self, r#type: PieceType) -> Bitboard {
+        self.pieces[r#type as usize - 1]
+    }
+
+    pub fn pieces_of(&self, r#type: PieceType, color: Color) -> Bitboard {
+        self.pieces(r#type) & self.occupied_by(color)
+    }
+
+    pub fn occupied_by(&self, color: Color) -> Bitboard {
+        self.colors[color_index(color)]
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
     }
 
     pub fn move_piece(&mut self, from: Pos, to: Pos) -> bool {
@@ -82,170 +208,138 @@ impl Board {
     }
 
     pub fn find_first_of_type(&self, r#type: PieceType, color: Color) -> Option<Pos> {
-        let piece = TaggedPiece::new(r#type, color);
-        for (i, p) in self.0.iter().enumerate() {
-            if p == &piece {
-                return Some(Pos::new_index(i as u8));
-            }
-        }
-
-        None
+        self.pieces_of(r#type, color).first()
     }
 
     pub fn find_king(&self, color: Color) -> Pos {
         self.find_first_of_type(PieceType::King, color).unwrap()
     }
 
-    pub fn after_move(&self, from: Pos, r#move: Move, color: Color) -> Self {
-        let mut board = *self;
-        match r#move {
-            Move::Move(to) => {
-                board.move_piece(from, to);
-            }
-            Move::KingSideCastling => {
-                let y: u8 = if color == Color::White { 0 } else { 7 };
-
-                let rook_pos = Pos::new_xy(7, y);
-                board.move_piece(rook_pos, Pos::new_xy(5, y));
+    // Neither side can ever checkmate, no matter how badly the other plays
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut minors = 0;
+        let mut knights = 0;
+        let mut bishop_squares = [false; 2];
 
-                let king_pos = Pos::new_xy(4, y);
-                board.move_piece(king_pos, Pos::new_xy(6, y));
+        for (i, piece) in self.squares.iter().enumerate() {
+            if piece.is_empty() {
+                continue;
             }
-            Move::QueenSideCastling => {
-                let y: u8 = if color == Color::White { 0 } else { 7 };
-
-                let rook_pos = Pos::new_xy(0, y);
-                board.move_piece(rook_pos, Pos::new_xy(3, y));
 
-                let king_pos = Pos::new_xy(4, y);
-                board.move_piece(king_pos, Pos::new_xy(2, y));
-            }
-            Move::PawnPromotion(r#type, to) => {
-                board.move_piece(from, to);
-                board.set_pos(to, TaggedPiece::new(r#type, color));
-            }
-            Move::EnPassant(to) => {
-                let dir = if color == Color::White { -1 } else { 1 };
-                board.move_piece(from, to);
-
-                let (x, y) = to.xy();
-                let remove_pos = Pos::new_xy(x, (y as i8 + dir) as u8);
-                board.set_pos(remove_pos, TaggedPiece::empty());
+            match piece.get_type() {
+                PieceType::King => {}
+                PieceType::Knight => {
+                    minors += 1;
+                    knights += 1;
+                }
+                PieceType::Bishop => {
+                    minors += 1;
+                    let pos = Pos::new_index(i as u8);
+                    bishop_squares[((pos.x() + pos.y()) % 2) as usize] = true;
+                }
+                _ => return false,
             }
-            _ => panic!("Unimplemented move {:?}", r#move),
         }
 
-        board
+        minors <= 1 || (knights == 0 && !(bishop_squares[0] && bishop_squares[1]))
     }
 
-    pub fn pos_in_danger(&self, pos: Pos, color: Color) -> bool {
-        let first_enemy_piece = |dir_x: i8, dir_y: i8| {
-            let mut i: i8 = 1;
-            loop {
-                if let Some(new_pos) = pos.move_xy(dir_x * i, dir_y * i) {
-                    let piece = self.at_pos(new_pos);
-
-                    if !piece.is_empty() {
-                        if piece.color() == color {
-                            return None;
-                        } else {
-                            return Some(new_pos);
-                        }
-                    }
-                } else {
-                    return None;
-                }
+    pub fn after_move(&self, from: Pos, r#move: Move, color: Color) -> Self {
+        let mut board = *self;
+        board.make_move(from, r#move, color);
+        board
+    }
 
-                i += 1;
-            }
+    pub fn make_move(&mut self, from: Pos, r#move: Move, color: Color) -> MoveUndo {
+        let moved = self.at_pos(from);
+        let captured = match r#move {
+            Move::Move(to) | Move::PawnPromotion(_, to) => self.at_pos(to),
+            Move::EnPassant(to) => self.at_xy(to.x(), from.y()),
+            _ => TaggedPiece::empty(),
         };
-
-        let diag = |config: &(i8, i8, Color)| {
-            if let Some(hit) = first_enemy_piece(config.0, config.1) {
-                let piece = self.at_pos(hit);
-                let ptype = piece.get_type();
-                if ptype == PieceType::Bishop || ptype == PieceType::Queen {
-                    return true;
-                }
-
-                let dist_x = hit.distance_x(&pos);
-                let dist_y = hit.distance_y(&pos);
-
-                if dist_x == 1
-                    && dist_y == 1
-                    && (ptype == PieceType::King || (color == config.2 && ptype == PieceType::Pawn))
-                {
-                    return true;
-                }
-            }
-            false
+        let rook_from = match r#move {
+            Move::KingSideCastling => self.castling_rook(color, true),
+            Move::QueenSideCastling => self.castling_rook(color, false),
+            _ => None,
         };
 
-        let dirs: [(i8, i8, Color); 4] = [
-            (1, 1, Color::White),
-            (-1, 1, Color::White),
-            (1, -1, Color::Black),
-            (-1, -1, Color::Black),
-        ];
-
-        for d in dirs.iter() {
-            if diag(d) {
-                return true;
+        match r#move {
+            Move::Move(to) => {
+                self.move_piece(from, to);
+            }
+            Move::KingSideCastling | Move::QueenSideCastling => {
+                let (king_to, rook_to) = castling_targets(from, r#move);
+                let rook = rook_from.unwrap();
+
+                // Either piece can already stand on the other's target square, or its own
+                self.set_pos(from, TaggedPiece::empty());
+                self.set_pos(rook, TaggedPiece::empty());
+                self.set_pos(king_to, moved.non_original());
+                self.set_pos(rook_to, TaggedPiece::new(PieceType::Rook, color));
             }
+            Move::PawnPromotion(r#type, to) => {
+                self.move_piece(from, to);
+                self.set_pos(to, TaggedPiece::new(r#type, color));
+            }
+            Move::EnPassant(to) => {
+                self.move_piece(from, to);
+                self.set_pos(Pos::new_xy(to.x(), from.y()), TaggedPiece::empty());
+            }
+            _ => panic!("Unimplemented move {:?}", r#move),
         }
 
-        let straight = |dir: &(i8, i8)| {
-            if let Some(hit) = first_enemy_piece(dir.0, dir.1) {
-                let piece = self.at_pos(hit);
-                let ptype = piece.get_type();
-
-                if ptype == PieceType::Rook || ptype == PieceType::Queen {
-                    return true;
-                }
+        MoveUndo {
+            moved,
+            captured,
+            rook_from,
+        }
+    }
 
-                let dist_x = hit.distance_x(&pos);
-                let dist_y = hit.distance_y(&pos);
-                if (dist_x + dist_y == 1) && ptype == PieceType::King {
-                    return true;
-                }
+    pub fn unmake_move(&mut self, from: Pos, r#move: Move, color: Color, undo: MoveUndo) {
+        match r#move {
+            Move::Move(to) | Move::PawnPromotion(_, to) => {
+                self.set_pos(from, undo.moved);
+                self.set_pos(to, undo.captured);
             }
-            false
-        };
-
-        let dirs: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
-
-        for d in dirs.iter() {
-            if straight(d) {
-                return true;
+            Move::EnPassant(to) => {
+                self.set_pos(from, undo.moved);
+                self.set_pos(to, TaggedPiece::empty());
+                self.set_pos(Pos::new_xy(to.x(), from.y()), undo.captured);
+            }
+            Move::KingSideCastling | Move::QueenSideCastling => {
+                let (king_to, rook_to) = castling_targets(from, r#move);
+
+                // Castling is only legal with both pieces unmoved
+                self.set_pos(king_to, TaggedPiece::empty());
+                self.set_pos(rook_to, TaggedPiece::empty());
+                self.set_pos(from, undo.moved);
+                self.set_pos(
+                    undo.rook_from.unwrap(),
+                    TaggedPiece::original(PieceType::Rook, color),
+                );
             }
+            _ => panic!("Unimplemented move {:?}", r#move),
         }
+    }
 
-        let dirs: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
-
-        for (x, y) in dirs.iter() {
-            let dir_move = |off_x, off_y| {
-                if let Some(new_pos) = pos.move_xy(off_x, off_y) {
-                    let piece = self.at_pos(new_pos);
-                    if !piece.is_empty()
-                        && piece.color() != color
-                        && piece.get_type() == PieceType::Knight
-                    {
-                        return true;
-                    }
-                }
-                false
-            };
+    pub fn pos_in_danger(&self, pos: Pos, color: Color) -> bool {
+        !self.attackers(pos, color.flip()).is_empty()
+    }
 
-            if dir_move(x * 2, *y) {
-                return true;
-            }
+    // Pieces of the given color attacking pos
+    pub fn attackers(&self, pos: Pos, color: Color) -> Bitboard {
+        self.attackers_with(pos, color, self.occupied())
+    }
 
-            if dir_move(*x, y * 2) {
-                return true;
-            }
-        }
+    // Same as attackers, with sliders blocked by the given occupancy instead
+    pub fn attackers_with(&self, pos: Pos, color: Color, occupied: Bitboard) -> Bitboard {
+        let queens = self.pieces_of(PieceType::Queen, color);
 
-        false
+        (knight_attacks(pos) & self.pieces_of(PieceType::Knight, color))
+            | (king_attacks(pos) & self.pieces_of(PieceType::King, color))
+            | (pawn_attacks(pos, color.flip()) & self.pieces_of(PieceType::Pawn, color))
+            | (rook_attacks(pos, occupied) & (self.pieces_of(PieceType::Rook, color) | queens))
+            | (bishop_attacks(pos, occupied) & (self.pieces_of(PieceType::Bishop, color) | queens))
     }
 
     pub fn print_ascii(&self, color: Color) {
@@ -0,0 +1,65 @@
+use crate::bitboard::Bitboard;
+use crate::{Board, PieceType, Pos, TaggedPiece};
+
+const PIECE_ORDER: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+fn value(piece: TaggedPiece) -> i32 {
+    if piece.is_empty() {
+        0
+    } else {
+        piece.get_type().value()
+    }
+}
+
+impl Board {
+    // Material the piece on from wins by capturing on to, when both sides keep recapturing
+    // with their least valuable piece for as long as it pays. Pieces lined up behind an
+    // attacker join in once it has moved.
+    pub fn see(&self, from: Pos, to: Pos) -> i32 {
+        let mut gain = [0; 32];
+        let mut depth = 0;
+
+        let mut occupied = self.occupied();
+        let mut attacker = from;
+        let mut color = self.at_pos(from).color();
+        gain[0] = value(self.at_pos(to));
+
+        loop {
+            depth += 1;
+            // What the capture is worth if the piece that made it is taken back
+            gain[depth] = value(self.at_pos(attacker)) - gain[depth - 1];
+            if (-gain[depth - 1]).max(gain[depth]) < 0 {
+                break;
+            }
+
+            occupied ^= Bitboard::from_pos(attacker);
+            color = color.flip();
+
+            // Sliders are looked up with the current occupancy, which reveals x-rays
+            let attackers = self.attackers_with(to, color, occupied) & occupied;
+            match PIECE_ORDER
+                .iter()
+                .map(|&r#type| attackers & self.pieces(r#type))
+                .find(|attackers| !attackers.is_empty())
+            {
+                Some(attackers) => attacker = attackers.first().unwrap(),
+                None => break,
+            }
+        }
+
+        // The last capture is never made, nobody could take back
+        while depth > 1 {
+            depth -= 1;
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        }
+
+        gain[0]
+    }
+}
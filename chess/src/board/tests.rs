@@ -61,3 +61,328 @@ fn from_str() {
 
     assert_eq!(default, from_str.unwrap());
 }
+
+#[test]
+fn from_fen() {
+    let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(board.unwrap(), Board::default());
+
+    const BOARD_STATE: &str = "
+        .  .  .  .  K  .  .  R
+        .  .  .  .  .  .  .  .
+        .  .  .  .  .  .  .  .
+        .  .  .  .  p  .  .  .
+        .  .  .  .  .  .  .  .
+        .  .  .  .  .  .  .  .
+        .  .  .  .  .  .  .  .
+        .  .  .  .  k  .  .  .";
+
+    let board = Board::from_fen("4k3/8/8/8/4p3/8/8/4K2R").unwrap();
+    assert_eq!(board, Board::from_str(BOARD_STATE).unwrap());
+    assert!(!board.at_xy(4, 0).is_original());
+    assert!(!board.at_xy(7, 0).is_original());
+
+    let board = Board::from_fen("4k3/8/8/8/4p3/8/8/4K2R w K - 0 1").unwrap();
+    assert!(board.at_xy(4, 0).is_original());
+    assert!(board.at_xy(7, 0).is_original());
+}
+
+#[test]
+fn from_fen_invalid() {
+    assert_eq!(Board::from_fen(""), Err(ParseFenError::MissingField));
+    assert_eq!(
+        Board::from_fen("8/8/8/8/8/8/8"),
+        Err(ParseFenError::InvalidPlacement)
+    );
+    assert_eq!(
+        Board::from_fen("8/8/8/8/8/8/8/9"),
+        Err(ParseFenError::InvalidPlacement)
+    );
+    assert_eq!(
+        Board::from_fen("8/8/8/8/8/8/8/ppppppppp"),
+        Err(ParseFenError::InvalidPlacement)
+    );
+    assert_eq!(
+        Board::from_fen("8/8/8/8/8/8/8/7"),
+        Err(ParseFenError::InvalidPlacement)
+    );
+    assert_eq!(
+        Board::from_fen("8/8/8/8/8/8/8/7x"),
+        Err(ParseFenError::InvalidPlacement)
+    );
+    // Pawns can't stand on the first or last rank
+    assert_eq!(
+        Board::from_fen("P3k3/8/8/8/8/8/8/4K3"),
+        Err(ParseFenError::InvalidPlacement)
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K2p"),
+        Err(ParseFenError::InvalidPlacement)
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1"),
+        Err(ParseFenError::InvalidCastling)
+    );
+}
+
+#[test]
+fn to_fen() {
+    let board = Board::default();
+    assert_eq!(
+        board.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR"
+    );
+    assert_eq!(board.castling_fen(), "KQkq");
+
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R";
+    let board = Board::from_fen(fen).unwrap();
+    assert_eq!(board.to_fen(), fen);
+    assert_eq!(board.castling_fen(), "-");
+
+    let board = Board::from_fen(&format!("{} w Kq - 0 1", fen)).unwrap();
+    assert_eq!(board.castling_fen(), "Kq");
+}
+
+#[test]
+fn chess960_start_positions() {
+    assert!(Board::chess960(518).unwrap() == Board::default());
+    assert_eq!(
+        Board::chess960(0).unwrap().to_fen(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR"
+    );
+    assert_eq!(
+        Board::chess960(959).unwrap().to_fen(),
+        "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB"
+    );
+    assert!(Board::chess960(960).is_none());
+
+    let mut fens: Vec<String> = (0..960)
+        .map(|i| Board::chess960(i).unwrap().to_fen())
+        .collect();
+    fens.sort();
+    fens.dedup();
+    assert_eq!(fens.len(), 960);
+
+    // Both rooks keep their castling rights wherever they start
+    let board = Board::chess960(0).unwrap();
+    assert_eq!(board.castling_fen(), "KQkq");
+    assert_eq!(board.shredder_castling_fen(), "HFhf");
+    assert_eq!(
+        board.castling_rook(Color::White, false),
+        Some(Pos::new_xy(5, 0))
+    );
+    assert!(board.has_chess960_castling());
+    assert!(!Board::default().has_chess960_castling());
+}
+
+#[test]
+fn chess960_castling_fen() {
+    let placement = "1r2k1rr/8/8/8/8/8/8/1R2K1RR";
+
+    // X-FEN letters for the outermost rooks, Shredder-FEN files for any rook
+    let board = Board::from_fen(&format!("{} w KQkq - 0 1", placement)).unwrap();
+    assert_eq!(board.shredder_castling_fen(), "HBhb");
+    assert_eq!(
+        Board::from_fen(&format!("{} w HBhb - 0 1", placement)).unwrap(),
+        board
+    );
+    assert_eq!(
+        Board::from_fen(&format!("{} w HBhb - 0 1", placement))
+            .unwrap()
+            .castling_fen(),
+        "KQkq"
+    );
+
+    // The inner rook needs its file in X-FEN too
+    let board = Board::from_fen(&format!("{} w Gg - 0 1", placement)).unwrap();
+    assert_eq!(board.castling_fen(), "Gg");
+    assert_eq!(
+        board.castling_rook(Color::White, true),
+        Some(Pos::new_xy(6, 0))
+    );
+    assert_eq!(board.castling_rook(Color::White, false), None);
+
+    for castling in ["A", "E", "c", "Kx"].iter() {
+        assert_eq!(
+            Board::from_fen(&format!("{} w {} - 0 1", placement, castling)),
+            Err(ParseFenError::InvalidCastling)
+        );
+    }
+}
+
+#[test]
+fn insufficient_material() {
+    let insufficient = [
+        "4k3/8/8/8/8/8/8/4K3",
+        "4k3/8/8/8/8/8/8/2B1K3",
+        "4k3/8/8/8/8/8/8/1N2K3",
+        "2b1k3/8/8/8/8/8/8/3BK3",
+        "4k3/8/8/8/8/8/8/B1B1K1B1",
+    ];
+
+    for fen in insufficient.iter() {
+        assert!(
+            Board::from_fen(fen).unwrap().has_insufficient_material(),
+            "{}",
+            fen
+        );
+    }
+
+    let sufficient = [
+        "4k3/8/8/8/8/8/8/2B1KB2",
+        "4k3/8/8/8/8/8/8/1NN1K3",
+        "1n2k3/8/8/8/8/8/8/2B1K3",
+        "4k3/8/8/8/8/8/4P3/4K3",
+        "4k3/8/8/8/8/8/8/R3K3",
+        "3qk3/8/8/8/8/8/8/4K3",
+    ];
+
+    for fen in sufficient.iter() {
+        assert!(
+            !Board::from_fen(fen).unwrap().has_insufficient_material(),
+            "{}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn zobrist() {
+    let board = Board::default();
+    let start = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq").unwrap();
+    assert_eq!(board.zobrist(), start.zobrist());
+
+    let no_castling = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap();
+    assert_eq!(board.placement_zobrist(), no_castling.placement_zobrist());
+    assert_ne!(board.zobrist(), no_castling.zobrist());
+
+    let moved = board.after_move(
+        Pos::new_xy(4, 1),
+        Move::Move(Pos::new_xy(4, 3)),
+        Color::White,
+    );
+    assert_ne!(board.zobrist(), moved.zobrist());
+    assert_eq!(
+        board.placement_zobrist()
+            ^ board.move_zobrist(
+                Pos::new_xy(4, 1),
+                Move::Move(Pos::new_xy(4, 3)),
+                Color::White
+            ),
+        moved.placement_zobrist()
+    );
+}
+
+#[test]
+fn hash_matches_eq() {
+    use std::collections::HashSet;
+
+    let mut boards = HashSet::new();
+    boards.insert(Board::default());
+    boards.insert(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap());
+    assert_eq!(boards.len(), 1);
+
+    boards.insert(Board::from_fen("4k3/8/8/8/8/8/8/4K3").unwrap());
+    assert_eq!(boards.len(), 2);
+}
+
+#[test]
+fn bitboards_follow_moves() {
+    let board = Board::default();
+    assert_eq!(board.occupied().count(), 32);
+    assert_eq!(board.pieces(PieceType::Pawn).count(), 16);
+    assert_eq!(
+        board.pieces_of(PieceType::Knight, Color::Black).first(),
+        Some(Pos::new_xy(1, 7))
+    );
+
+    let board = board.after_move(
+        Pos::new_xy(4, 1),
+        Move::Move(Pos::new_xy(4, 3)),
+        Color::White,
+    );
+    let board = board.after_move(
+        Pos::new_xy(3, 6),
+        Move::Move(Pos::new_xy(3, 4)),
+        Color::Black,
+    );
+    let board = board.after_move(
+        Pos::new_xy(4, 3),
+        Move::Move(Pos::new_xy(3, 4)),
+        Color::White,
+    );
+
+    assert_eq!(board.occupied().count(), 31);
+    assert_eq!(board.pieces_of(PieceType::Pawn, Color::Black).count(), 7);
+    assert!(board
+        .pieces_of(PieceType::Pawn, Color::White)
+        .contains(Pos::new_xy(3, 4)));
+    assert!(!board.occupied().contains(Pos::new_xy(4, 3)));
+
+    for i in 0..64 {
+        let pos = Pos::new_index(i);
+        let piece = board.at_pos(pos);
+        assert_eq!(board.occupied().contains(pos), !piece.is_empty());
+        if !piece.is_empty() {
+            assert!(board
+                .pieces_of(piece.get_type(), piece.color())
+                .contains(pos));
+        }
+    }
+}
+
+#[test]
+fn attackers() {
+    let board = Board::from_fen("4k3/8/8/3p4/2N1R3/8/1B6/4K3").unwrap();
+    let e5 = Pos::new_xy(4, 4);
+
+    let white: Vec<Pos> = board.attackers(e5, Color::White).collect();
+    assert_eq!(
+        white,
+        [Pos::new_xy(1, 1), Pos::new_xy(2, 3), Pos::new_xy(4, 3)]
+    );
+    assert!(board.pos_in_danger(e5, Color::Black));
+
+    let e4 = Pos::new_xy(4, 3);
+    assert_eq!(
+        board.attackers(e4, Color::Black).first(),
+        Some(Pos::new_xy(3, 4))
+    );
+    assert!(!board.pos_in_danger(Pos::new_xy(7, 6), Color::Black));
+}
+
+#[test]
+fn see() {
+    let see = |fen: &str, from: &str, to: &str| {
+        let board = Board::from_fen(fen).unwrap();
+        board.see(Pos::from_str(from).unwrap(), Pos::from_str(to).unwrap())
+    };
+
+    // Free pawn
+    assert_eq!(see("4k3/8/8/3p4/8/8/8/3RK3", "d1", "d5"), 100);
+    // Pawn defended by a pawn
+    assert_eq!(see("4k3/8/4p3/3p4/8/8/8/3RK3", "d1", "d5"), -400);
+    // Even knight trade
+    assert_eq!(see("4k3/8/4p3/3n4/8/4N3/8/4K3", "e3", "d5"), 0);
+    // Quiet move to an attacked square
+    assert_eq!(see("4k3/8/4p3/8/8/8/8/3RK3", "d1", "d5"), -500);
+    // The defender can't take back without losing more
+    assert_eq!(see("3qk3/8/8/3p4/8/8/3R4/3QK3", "d2", "d5"), 100);
+    // Black captures too
+    assert_eq!(see("4k3/8/8/4n3/3P4/2P5/8/4K3", "e5", "d4"), -220);
+}
+
+#[test]
+fn see_x_rays() {
+    let see = |fen: &str, from: &str, to: &str| {
+        let board = Board::from_fen(fen).unwrap();
+        board.see(Pos::from_str(from).unwrap(), Pos::from_str(to).unwrap())
+    };
+
+    // The second rook recaptures through the first
+    assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/3RK3", "d2", "d5"), 100);
+    // A queen behind the defending rook keeps the pawn safe
+    assert_eq!(see("3qk3/3r4/8/3p4/8/8/3R4/3RK3", "d2", "d5"), -400);
+    // Bishop behind a pawn on the diagonal
+    assert_eq!(see("4k3/8/2p5/3n4/4P3/5B2/8/4K3", "e4", "d5"), 320);
+}
@@ -0,0 +1,147 @@
+use super::castling_targets;
+use crate::{Board, Color, Move, Pos, TaggedPiece};
+
+use std::hash::{Hash, Hasher};
+
+struct Keys {
+    pieces: [[u64; 64]; 12],
+    side: u64,
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+}
+
+// splitmix64 with a fixed seed so keys are the same on every run
+const fn next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn generate_keys() -> Keys {
+    let mut state = 0x1D0C_5EED_u64;
+    let mut keys = Keys {
+        pieces: [[0; 64]; 12],
+        side: 0,
+        castling: [0; 4],
+        en_passant: [0; 8],
+    };
+
+    let mut piece = 0;
+    while piece < 12 {
+        let mut square = 0;
+        while square < 64 {
+            keys.pieces[piece][square] = next_key(&mut state);
+            square += 1;
+        }
+        piece += 1;
+    }
+
+    keys.side = next_key(&mut state);
+
+    let mut i = 0;
+    while i < 4 {
+        keys.castling[i] = next_key(&mut state);
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < 8 {
+        keys.en_passant[i] = next_key(&mut state);
+        i += 1;
+    }
+
+    keys
+}
+
+const KEYS: Keys = generate_keys();
+
+pub fn piece_key(piece: TaggedPiece, pos: Pos) -> u64 {
+    if piece.is_empty() {
+        return 0;
+    }
+
+    let color = if piece.color() == Color::White { 0 } else { 6 };
+    KEYS.pieces[color + piece.get_type() as usize - 1][pos.index()]
+}
+
+// Included when black is to move
+pub fn side_key() -> u64 {
+    KEYS.side
+}
+
+// One key per side of the king, a side can only ever have one castling rook
+pub fn castling_key(color: Color, king_side: bool) -> u64 {
+    let side = if king_side { 0 } else { 1 };
+    let color = if color == Color::White { 0 } else { 2 };
+    KEYS.castling[color + side]
+}
+
+pub fn en_passant_key(x: u8) -> u64 {
+    KEYS.en_passant[x as usize]
+}
+
+impl Board {
+    // Piece placement and castling rights
+    pub fn zobrist(&self) -> u64 {
+        self.placement_zobrist() ^ self.castling_zobrist()
+    }
+
+    pub fn placement_zobrist(&self) -> u64 {
+        self.squares.iter().enumerate().fold(0, |key, (i, &piece)| {
+            key ^ piece_key(piece, Pos::new_index(i as u8))
+        })
+    }
+
+    pub fn castling_zobrist(&self) -> u64 {
+        let mut key = 0;
+        for &color in [Color::White, Color::Black].iter() {
+            for &king_side in [true, false].iter() {
+                if self.castling_rook(color, king_side).is_some() {
+                    key ^= castling_key(color, king_side);
+                }
+            }
+        }
+
+        key
+    }
+
+    // Difference in placement key caused by a move, castling rights excluded
+    pub fn move_zobrist(&self, from: Pos, r#move: Move, color: Color) -> u64 {
+        let piece = self.at_pos(from);
+        let moved = |from: Pos, to: Pos| {
+            let piece = self.at_pos(from);
+            piece_key(piece, from) ^ piece_key(piece, to)
+        };
+
+        match r#move {
+            Move::Move(to) => moved(from, to) ^ piece_key(self.at_pos(to), to),
+            Move::PawnPromotion(r#type, to) => {
+                piece_key(piece, from)
+                    ^ piece_key(self.at_pos(to), to)
+                    ^ piece_key(TaggedPiece::new(r#type, color), to)
+            }
+            Move::EnPassant(to) => {
+                let captured = Pos::new_xy(to.x(), from.y());
+                moved(from, to) ^ piece_key(self.at_pos(captured), captured)
+            }
+            Move::KingSideCastling | Move::QueenSideCastling => {
+                let rook = self
+                    .castling_rook(color, r#move == Move::KingSideCastling)
+                    .unwrap();
+                let (king_to, rook_to) = castling_targets(from, r#move);
+
+                moved(from, king_to) ^ moved(rook, rook_to)
+            }
+            Move::None => 0,
+        }
+    }
+}
+
+// Consistent with PartialEq, which only compares placement
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.placement_zobrist());
+    }
+}
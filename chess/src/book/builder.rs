@@ -0,0 +1,103 @@
+use super::{encode_move, Book, BookEntry, PolyglotKeys};
+use crate::game::Game;
+use crate::pgn::{play_pgn_move, ParsePgnError, PgnReader};
+
+use std::collections::HashMap;
+
+// Counts how often each move was played from each position in a set of games.
+// Weights in the finished book are those counts, scaled down if they don't fit
+// in 16 bits.
+pub struct BookBuilder {
+    keys: PolyglotKeys,
+    max_ply: usize,
+    min_count: u32,
+    counts: HashMap<(u64, u16), u32>,
+}
+
+impl BookBuilder {
+    pub fn new(keys: PolyglotKeys) -> Self {
+        Self {
+            keys,
+            max_ply: 30,
+            min_count: 1,
+            counts: HashMap::new(),
+        }
+    }
+
+    // Only the first plies of each game make it into the book
+    pub fn with_max_ply(mut self, max_ply: usize) -> Self {
+        self.max_ply = max_ply;
+        self
+    }
+
+    // Moves played fewer times than this are left out
+    pub fn with_min_count(mut self, min_count: u32) -> Self {
+        self.min_count = min_count;
+        self
+    }
+
+    fn count(&mut self, key: u64, r#move: u16) {
+        *self.counts.entry((key, r#move)).or_insert(0) += 1;
+    }
+
+    pub fn add_game(&mut self, game: &Game) {
+        let mut replay = match Game::from_fen(game.start_fen()) {
+            Ok(replay) => replay,
+            Err(_) => return,
+        };
+
+        for (from, r#move) in game.played_moves().take(self.max_ply) {
+            let key = self.keys.hash(&replay);
+            self.count(key, encode_move(replay.board(), from, r#move));
+            replay.play(from, r#move);
+        }
+    }
+
+    // Adds every game of a PGN collection, returning how many there were
+    pub fn add_pgn(&mut self, pgn: &str) -> Result<usize, ParsePgnError> {
+        let mut games = 0;
+
+        for pgn_game in PgnReader::new(pgn) {
+            let pgn_game = pgn_game?;
+            let mut game = pgn_game.starting_game()?;
+
+            for pgn_move in pgn_game.moves.iter().take(self.max_ply) {
+                let key = self.keys.hash(&game);
+                let board = *game.board();
+                play_pgn_move(&mut game, pgn_move)?;
+
+                let (from, r#move) = game.played_moves().last().unwrap();
+                self.count(key, encode_move(&board, from, r#move));
+            }
+
+            games += 1;
+        }
+
+        Ok(games)
+    }
+
+    pub fn build(&self) -> Book {
+        let max = self.counts.values().copied().max().unwrap_or(0) as u64;
+        let scale = |count: u32| {
+            if max <= u16::MAX as u64 {
+                count as u16
+            } else {
+                (count as u64 * u16::MAX as u64 / max).max(1) as u16
+            }
+        };
+
+        let entries = self
+            .counts
+            .iter()
+            .filter(|(_, &count)| count >= self.min_count)
+            .map(|(&(key, r#move), &count)| BookEntry {
+                key,
+                r#move,
+                weight: scale(count),
+                learn: 0,
+            })
+            .collect();
+
+        Book::new(self.keys.clone(), entries)
+    }
+}
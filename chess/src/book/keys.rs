@@ -0,0 +1,130 @@
+use super::random64::RANDOM64;
+use super::BookError;
+use crate::game::Game;
+use crate::{Color, PieceType, Pos};
+
+use std::path::Path;
+
+pub const KEY_COUNT: usize = 781;
+
+const CASTLING: usize = 768;
+const EN_PASSANT: usize = 772;
+const TURN: usize = 780;
+
+// 768 piece keys, 4 castling keys, 8 en passant keys and the side to move key.
+// Books only match positions hashed with the same table, which for real Polyglot
+// books is the standard one.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PolyglotKeys(Vec<u64>);
+
+impl Default for PolyglotKeys {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl PolyglotKeys {
+    pub fn standard() -> Self {
+        Self(RANDOM64.to_vec())
+    }
+
+    pub fn from_keys(keys: Vec<u64>) -> Result<Self, BookError> {
+        if keys.len() != KEY_COUNT {
+            return Err(BookError::InvalidKeys);
+        }
+
+        Ok(Self(keys))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if bytes.len() != KEY_COUNT * 8 {
+            return Err(BookError::InvalidKeys);
+        }
+
+        let keys = bytes
+            .chunks_exact(8)
+            .map(|chunk| {
+                let mut key = [0; 8];
+                key.copy_from_slice(chunk);
+                u64::from_be_bytes(key)
+            })
+            .collect();
+
+        Ok(Self(keys))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BookError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|key| key.to_be_bytes()).collect()
+    }
+
+    pub fn hash(&self, game: &Game) -> u64 {
+        let board = game.board();
+        let mut key = 0;
+
+        for i in 0..64 {
+            let piece = board.at_index(i);
+            if !piece.is_empty() {
+                key ^= self.0[64 * piece_kind(piece.get_type(), piece.color()) + i];
+            }
+        }
+
+        let rights = [
+            (Color::White, true),
+            (Color::White, false),
+            (Color::Black, true),
+            (Color::Black, false),
+        ];
+        for (i, &(color, king_side)) in rights.iter().enumerate() {
+            if board.castling_rook(color, king_side).is_some() {
+                key ^= self.0[CASTLING + i];
+            }
+        }
+
+        if let Some(target) = game.en_passant() {
+            if pawn_beside(game, target) {
+                key ^= self.0[EN_PASSANT + target.x() as usize];
+            }
+        }
+
+        if game.current_color() == Color::White {
+            key ^= self.0[TURN];
+        }
+
+        key
+    }
+}
+
+// Black pawn, white pawn, black knight, ... white king
+fn piece_kind(r#type: PieceType, color: Color) -> usize {
+    let order = match r#type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+
+    order * 2 + if color == Color::White { 1 } else { 0 }
+}
+
+// Polyglot hashes the en passant file whenever a pawn could take, even if the
+// capture would leave the king in check
+fn pawn_beside(game: &Game, target: Pos) -> bool {
+    let color = game.current_color();
+    let y = if color == Color::White { 4 } else { 3 };
+
+    [-1, 1].iter().any(|dx| {
+        let x = target.x() as i8 + dx;
+        if !(0..8).contains(&x) {
+            return false;
+        }
+
+        let piece = game.at_xy(x as u8, y);
+        !piece.is_empty() && piece.get_type() == PieceType::Pawn && piece.color() == color
+    })
+}
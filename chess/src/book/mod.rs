@@ -0,0 +1,246 @@
+use crate::game::Game;
+use crate::{Board, Move, PieceType, Pos};
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+
+pub mod builder;
+pub mod keys;
+mod random64;
+
+pub use builder::BookBuilder;
+pub use keys::PolyglotKeys;
+
+#[cfg(test)]
+mod tests;
+
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug)]
+pub enum BookError {
+    Io(std::io::Error),
+    InvalidKeys,
+    InvalidLength,
+}
+
+impl From<std::io::Error> for BookError {
+    fn from(error: std::io::Error) -> Self {
+        BookError::Io(error)
+    }
+}
+
+impl std::fmt::Display for BookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookError::Io(error) => write!(f, "{}", error),
+            BookError::InvalidKeys => write!(f, "expected {} keys", keys::KEY_COUNT),
+            BookError::InvalidLength => write!(f, "book size isn't a multiple of 16 bytes"),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+// One 16 byte record of a .bin file, all fields big endian
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BookEntry {
+    pub key: u64,
+    pub r#move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut key = [0; 8];
+        key.copy_from_slice(&bytes[0..8]);
+
+        Self {
+            key: u64::from_be_bytes(key),
+            r#move: u16::from_be_bytes([bytes[8], bytes[9]]),
+            weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+            learn: u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        }
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.key.to_be_bytes());
+        bytes.extend_from_slice(&self.r#move.to_be_bytes());
+        bytes.extend_from_slice(&self.weight.to_be_bytes());
+        bytes.extend_from_slice(&self.learn.to_be_bytes());
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BookMove {
+    pub from: Pos,
+    pub r#move: Move,
+    pub weight: u16,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Selection {
+    // Highest weight, the same move every time
+    Best,
+    // Chance proportional to weight, moves weighted 0 are never played
+    Weighted,
+    // Every move in the book equally likely
+    Uniform,
+}
+
+// Polyglot move: to file and rank in the low 6 bits, from file and rank in the
+// next 6 and the promotion piece above that. Castling is king takes own rook, the
+// board before the move tells which one in Chess960.
+pub fn encode_move(board: &Board, from: Pos, r#move: Move) -> u16 {
+    let color = board.at_pos(from).color();
+    let (to, promotion) = match r#move {
+        Move::Move(to) | Move::EnPassant(to) => (to, 0),
+        Move::PawnPromotion(r#type, to) => {
+            let promotion = match r#type {
+                PieceType::Knight => 1,
+                PieceType::Bishop => 2,
+                PieceType::Rook => 3,
+                _ => 4,
+            };
+            (to, promotion)
+        }
+        Move::KingSideCastling => {
+            let rook = board.castling_rook(color, true);
+            (rook.unwrap_or(Pos::new_xy(7, from.y())), 0)
+        }
+        Move::QueenSideCastling => {
+            let rook = board.castling_rook(color, false);
+            (rook.unwrap_or(Pos::new_xy(0, from.y())), 0)
+        }
+        Move::None => return 0,
+    };
+
+    (promotion << 12) | ((from.index() as u16) << 6) | to.index() as u16
+}
+
+// Finds the legal move a book move stands for, None if there isn't one
+pub fn decode_move(game: &Game, r#move: u16) -> Option<(Pos, Move)> {
+    game.legal_moves()
+        .map(|legal| (legal.from, legal.r#move))
+        .find(|&(from, legal)| encode_move(game.board(), from, legal) == r#move)
+}
+
+// A number for the random selections, different on every call
+pub fn random_roll() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+pub struct Book {
+    keys: PolyglotKeys,
+    // Sorted by key, each position's moves by descending weight
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn new(keys: PolyglotKeys, mut entries: Vec<BookEntry>) -> Self {
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        Self { keys, entries }
+    }
+
+    pub fn from_bytes(keys: PolyglotKeys, bytes: &[u8]) -> Result<Self, BookError> {
+        if bytes.len() & (ENTRY_SIZE - 1) != 0 {
+            return Err(BookError::InvalidLength);
+        }
+
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(BookEntry::from_bytes)
+            .collect();
+
+        Ok(Self::new(keys, entries))
+    }
+
+    pub fn load<P: AsRef<Path>>(keys: PolyglotKeys, path: P) -> Result<Self, BookError> {
+        Self::from_bytes(keys, &std::fs::read(path)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * ENTRY_SIZE);
+        for entry in self.entries.iter() {
+            entry.write(&mut bytes);
+        }
+        bytes
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BookError> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn keys(&self) -> &PolyglotKeys {
+        &self.keys
+    }
+
+    pub fn entries(&self) -> &[BookEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn key(&self, game: &Game) -> u64 {
+        self.keys.hash(game)
+    }
+
+    // Entries for the position that are legal in it, highest weight first
+    pub fn moves(&self, game: &Game) -> Vec<BookMove> {
+        let key = self.key(game);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                let (from, r#move) = decode_move(game, entry.r#move)?;
+                Some(BookMove {
+                    from,
+                    r#move,
+                    weight: entry.weight,
+                })
+            })
+            .collect()
+    }
+
+    pub fn pick(&self, game: &Game, selection: Selection) -> Option<BookMove> {
+        self.pick_with(game, selection, random_roll())
+    }
+
+    // Same as pick with the random number given, so the choice can be repeated
+    pub fn pick_with(&self, game: &Game, selection: Selection, roll: u64) -> Option<BookMove> {
+        let moves = self.moves(game);
+
+        match selection {
+            Selection::Best => moves.first().copied(),
+            Selection::Uniform if moves.is_empty() => None,
+            Selection::Uniform => Some(moves[(roll % moves.len() as u64) as usize]),
+            Selection::Weighted => {
+                let total: u64 = moves.iter().map(|r#move| r#move.weight as u64).sum();
+                if total == 0 {
+                    return None;
+                }
+
+                let mut roll = roll % total;
+                moves.into_iter().find(|r#move| {
+                    let weight = r#move.weight as u64;
+                    if roll < weight {
+                        true
+                    } else {
+                        roll -= weight;
+                        false
+                    }
+                })
+            }
+        }
+    }
+}
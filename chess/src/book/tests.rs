@@ -0,0 +1,273 @@
+use super::keys::KEY_COUNT;
+use super::*;
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for uci in moves.iter() {
+        let (from, r#move) = game.parse_uci(uci).unwrap();
+        game.play(from, r#move);
+    }
+}
+
+// Hashes to 1 exactly when the feature at index is part of the position
+fn single_key(index: usize) -> PolyglotKeys {
+    let mut keys = vec![0; KEY_COUNT];
+    keys[index] = 1;
+    PolyglotKeys::from_keys(keys).unwrap()
+}
+
+const GAMES: &str = "
+[Event \"1\"]
+1. e4 e5 2. Nf3 Nc6 1-0
+
+[Event \"2\"]
+1. e4 c5 2. Nf3 d6 0-1
+
+[Event \"3\"]
+1. d4 d5 2. c4 e6 1/2-1/2
+";
+
+#[test]
+fn move_encoding() {
+    let mut game = Game::from_fen("r3k3/7P/8/8/8/8/8/R3K2R w KQq - 0 1").unwrap();
+
+    let (from, r#move) = game.parse_uci("e1g1").unwrap();
+    // King takes own rook, e1h1
+    assert_eq!(encode_move(game.board(), from, r#move), (4 << 6) | 7);
+    assert_eq!(decode_move(&game, (4 << 6) | 7), Some((from, r#move)));
+    assert_eq!(
+        decode_move(&game, 4 << 6),
+        Some(game.parse_uci("e1c1").unwrap())
+    );
+
+    let (from, r#move) = game.parse_uci("h7h8n").unwrap();
+    assert_eq!(
+        encode_move(game.board(), from, r#move),
+        (1 << 12) | (55 << 6) | 63
+    );
+    assert_eq!(
+        decode_move(&game, (4 << 12) | (55 << 6) | 63),
+        Some(game.parse_uci("h7h8q").unwrap())
+    );
+
+    // Moves that aren't legal in the position don't decode
+    assert_eq!(decode_move(&game, (4 << 6) | 20), None);
+
+    // Chess960 rooks on a1 and g1 with the king on d1
+    let chess960 = Game::from_fen("1k6/8/8/8/8/8/8/R2K2R1 w AG - 0 1").unwrap();
+    for (uci, encoded) in [("d1g1", (3 << 6) | 6), ("d1a1", 3 << 6)] {
+        let (from, r#move) = chess960.parse_uci(uci).unwrap();
+        assert_eq!(encode_move(chess960.board(), from, r#move), encoded);
+        assert_eq!(decode_move(&chess960, encoded), Some((from, r#move)));
+    }
+
+    play(&mut game, &["e1g1"]);
+    assert_eq!(
+        decode_move(&game, (60 << 6) | 56),
+        Some(game.parse_uci("e8c8").unwrap())
+    );
+}
+
+#[test]
+fn key_layout() {
+    let game = Game::default();
+
+    // White pawn on e2, black king on e8, white can castle short, white to move
+    assert_eq!(single_key(64 + 12).hash(&game), 1);
+    assert_eq!(single_key(64 * 10 + 60).hash(&game), 1);
+    assert_eq!(single_key(768).hash(&game), 1);
+    assert_eq!(single_key(780).hash(&game), 1);
+    assert_eq!(single_key(64 + 28).hash(&game), 0);
+
+    let mut game = Game::default();
+    play(&mut game, &["e2e4"]);
+    assert_eq!(single_key(780).hash(&game), 0);
+    // No black pawn next to e4, so no en passant file
+    assert_eq!(single_key(772 + 4).hash(&game), 0);
+
+    let game = Game::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    assert_eq!(single_key(772 + 4).hash(&game), 1);
+    assert_eq!(single_key(768).hash(&game), 0);
+    assert_eq!(single_key(771).hash(&game), 0);
+
+    let game = Game::from_fen("r3k3/8/8/8/8/8/8/4K3 w q - 0 1").unwrap();
+    assert_eq!(single_key(771).hash(&game), 1);
+}
+
+#[test]
+fn standard_keys() {
+    // Reference keys from the Polyglot book format description
+    let positions: [(&[&str], u64); 9] = [
+        (&[], 0x463B96181691FC9C),
+        (&["e2e4"], 0x823C9B50FD114196),
+        (&["e2e4", "d7d5"], 0x0756B94461C50FB0),
+        (&["e2e4", "d7d5", "e4e5"], 0x662FAFB965DB29D4),
+        (&["e2e4", "d7d5", "e4e5", "f7f5"], 0x22A48B5A8E47FF78),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"],
+            0x652A607CA3F242C1,
+        ),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"],
+            0x00FDD303C946BDD9,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"],
+            0x3C8123EA7B067637,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"],
+            0x5C3F9B829B279560,
+        ),
+    ];
+
+    let keys = PolyglotKeys::default();
+    for (moves, key) in positions.iter() {
+        let mut game = Game::default();
+        play(&mut game, moves);
+        assert_eq!(keys.hash(&game), *key, "{:?}", moves);
+    }
+}
+
+#[test]
+fn keys_from_bytes() {
+    let keys = PolyglotKeys::standard();
+    assert_eq!(PolyglotKeys::from_bytes(&keys.to_bytes()).unwrap(), keys);
+
+    assert!(matches!(
+        PolyglotKeys::from_bytes(&[0; 780 * 8]),
+        Err(BookError::InvalidKeys)
+    ));
+    assert!(matches!(
+        PolyglotKeys::from_keys(vec![0; 782]),
+        Err(BookError::InvalidKeys)
+    ));
+}
+
+#[test]
+fn transpositions_share_keys() {
+    let keys = PolyglotKeys::standard();
+
+    let mut a = Game::default();
+    play(&mut a, &["g1f3", "g8f6", "b1c3"]);
+    let mut b = Game::default();
+    play(&mut b, &["b1c3", "g8f6", "g1f3"]);
+
+    assert_eq!(keys.hash(&a), keys.hash(&b));
+    assert_ne!(keys.hash(&a), keys.hash(&Game::default()));
+}
+
+#[test]
+fn build_from_pgn() {
+    let mut builder = BookBuilder::new(PolyglotKeys::standard()).with_max_ply(2);
+    assert_eq!(builder.add_pgn(GAMES).unwrap(), 3);
+
+    let book = builder.build();
+    let start = book.moves(&Game::default());
+    assert_eq!(start.len(), 2);
+    assert_eq!(start[0].r#move, Move::Move(Pos::new_xy(4, 3)));
+    assert_eq!(start[0].weight, 2);
+    assert_eq!(start[1].weight, 1);
+
+    let mut game = Game::default();
+    play(&mut game, &["e2e4"]);
+    assert_eq!(book.moves(&game).len(), 2);
+
+    // Past the ply limit
+    play(&mut game, &["e7e5"]);
+    assert!(book.moves(&game).is_empty());
+
+    let mut builder = BookBuilder::new(PolyglotKeys::standard()).with_min_count(2);
+    builder.add_pgn(GAMES).unwrap();
+    let book = builder.build();
+    assert_eq!(book.len(), 1);
+    assert_eq!(book.moves(&Game::default())[0].weight, 2);
+}
+
+#[test]
+fn build_from_games() {
+    let mut game = Game::default();
+    play(&mut game, &["d2d4", "g8f6", "c2c4"]);
+
+    let mut builder = BookBuilder::new(PolyglotKeys::standard());
+    builder.add_game(&game);
+    builder.add_game(&game);
+    let book = builder.build();
+
+    assert_eq!(book.len(), 3);
+    play(&mut game, &["e7e6"]);
+    game.undo(2);
+    assert_eq!(
+        book.moves(&game)[0],
+        BookMove {
+            from: Pos::new_xy(2, 1),
+            r#move: Move::Move(Pos::new_xy(2, 3)),
+            weight: 2,
+        }
+    );
+}
+
+#[test]
+fn pgn_errors() {
+    let mut builder = BookBuilder::new(PolyglotKeys::standard());
+    assert!(builder.add_pgn("1. e4 e5 2. Ke3 *").is_err());
+}
+
+#[test]
+fn bytes_round_trip() {
+    let mut builder = BookBuilder::new(PolyglotKeys::standard());
+    builder.add_pgn(GAMES).unwrap();
+    let book = builder.build();
+
+    let bytes = book.to_bytes();
+    assert_eq!(bytes.len(), book.len() * 16);
+
+    let loaded = Book::from_bytes(PolyglotKeys::standard(), &bytes).unwrap();
+    assert_eq!(loaded.entries(), book.entries());
+    assert!(loaded
+        .entries()
+        .windows(2)
+        .all(|pair| pair[0].key <= pair[1].key));
+
+    assert!(matches!(
+        Book::from_bytes(PolyglotKeys::standard(), &bytes[1..]),
+        Err(BookError::InvalidLength)
+    ));
+}
+
+#[test]
+fn selection() {
+    let game = Game::default();
+    let key = PolyglotKeys::standard().hash(&game);
+    let entry = |uci: &str, weight| {
+        let (from, r#move) = game.parse_uci(uci).unwrap();
+        BookEntry {
+            key,
+            r#move: encode_move(game.board(), from, r#move),
+            weight,
+            learn: 0,
+        }
+    };
+
+    let book = Book::new(
+        PolyglotKeys::standard(),
+        vec![entry("d2d4", 1), entry("e2e4", 3), entry("a2a3", 0)],
+    );
+    let picked = |selection, roll| {
+        let BookMove { from, r#move, .. } = book.pick_with(&game, selection, roll).unwrap();
+        game.to_uci(from, r#move)
+    };
+
+    assert_eq!(picked(Selection::Best, 0), "e2e4");
+    assert_eq!(picked(Selection::Weighted, 0), "e2e4");
+    assert_eq!(picked(Selection::Weighted, 2), "e2e4");
+    assert_eq!(picked(Selection::Weighted, 3), "d2d4");
+    assert_eq!(picked(Selection::Uniform, 2), "a2a3");
+
+    for _ in 0..20 {
+        assert_ne!(book.pick(&game, Selection::Weighted).unwrap().weight, 0);
+    }
+
+    let mut after = game.clone();
+    play(&mut after, &["e2e4"]);
+    assert_eq!(book.pick(&after, Selection::Uniform), None);
+}
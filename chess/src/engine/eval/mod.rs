@@ -0,0 +1,327 @@
+use crate::bitboard::{
+    bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks,
+    Bitboard,
+};
+use crate::{game::Game, Board, Color, Move, PieceType, Pos};
+
+use std::ops::{Add, AddAssign, Mul, Sub};
+
+mod tables;
+
+#[cfg(test)]
+mod tests;
+
+pub trait Evaluator {
+    // Centipawns from the side to move's point of view
+    fn evaluate(&self, game: &Game) -> i32;
+}
+
+// Middlegame and endgame value of a term, blended by the game phase
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+struct Score(i32, i32);
+
+impl Score {
+    fn taper(self, phase: i32) -> i32 {
+        (self.0 * phase + self.1 * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Score(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Score {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Score(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self {
+        Score(self.0 * rhs, self.1 * rhs)
+    }
+}
+
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+// All pieces on the board is phase 24, only kings and pawns is 0
+const PHASE_WEIGHTS: [i32; 6] = [0, 2, 1, 1, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+// Per square over the number a piece usually reaches
+const MOBILITY: [(PieceType, Score, i32); 4] = [
+    (PieceType::Knight, Score(4, 4), 4),
+    (PieceType::Bishop, Score(5, 5), 6),
+    (PieceType::Rook, Score(2, 4), 7),
+    (PieceType::Queen, Score(1, 2), 13),
+];
+
+const DOUBLED_PAWN: Score = Score(-10, -20);
+const ISOLATED_PAWN: Score = Score(-10, -15);
+// Indexed by how far the pawn has advanced
+const PASSED_PAWN: [Score; 8] = [
+    Score(0, 0),
+    Score(5, 10),
+    Score(10, 20),
+    Score(20, 40),
+    Score(35, 70),
+    Score(60, 120),
+    Score(100, 200),
+    Score(0, 0),
+];
+
+const PAWN_SHIELD: Score = Score(12, 0);
+// Indexed by PieceType as usize - 1, how dangerous a piece near the king is
+const KING_ATTACK_WEIGHTS: [i32; 6] = [0, 3, 2, 2, 5, 0];
+const MAX_KING_DANGER: i32 = 500;
+
+// Each term tapered by phase, from white's point of view
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Breakdown {
+    pub material: i32,
+    pub piece_squares: i32,
+    pub mobility: i32,
+    pub pawn_structure: i32,
+    pub king_safety: i32,
+    pub phase: i32,
+    pub player: Color,
+}
+
+impl Breakdown {
+    pub fn white_score(&self) -> i32 {
+        self.material + self.piece_squares + self.mobility + self.pawn_structure + self.king_safety
+    }
+
+    // What Evaluator::evaluate returns
+    pub fn score(&self) -> i32 {
+        if self.player == Color::White {
+            self.white_score()
+        } else {
+            -self.white_score()
+        }
+    }
+}
+
+impl std::fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "material        {:>6}", self.material)?;
+        writeln!(f, "piece squares   {:>6}", self.piece_squares)?;
+        writeln!(f, "mobility        {:>6}", self.mobility)?;
+        writeln!(f, "pawn structure  {:>6}", self.pawn_structure)?;
+        writeln!(f, "king safety     {:>6}", self.king_safety)?;
+        writeln!(f, "total (white)   {:>6}", self.white_score())?;
+        writeln!(f, "phase           {:>6}/{}", self.phase, MAX_PHASE)
+    }
+}
+
+// Material, piece-square tables, mobility, pawn structure and king safety
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct DefaultEvaluator;
+
+impl Evaluator for DefaultEvaluator {
+    fn evaluate(&self, game: &Game) -> i32 {
+        self.breakdown(game).score()
+    }
+}
+
+impl DefaultEvaluator {
+    pub fn breakdown(&self, game: &Game) -> Breakdown {
+        let board = game.board();
+        let phase = phase(board);
+        let term = |f: fn(&Board, Color) -> Score| {
+            (f(board, Color::White) - f(board, Color::Black)).taper(phase)
+        };
+
+        Breakdown {
+            material: term(material),
+            piece_squares: term(piece_squares),
+            mobility: mobility(game).taper(phase),
+            pawn_structure: term(pawn_structure),
+            king_safety: term(king_safety),
+            phase,
+            player: game.current_color(),
+        }
+    }
+}
+
+fn phase(board: &Board) -> i32 {
+    let phase = PIECE_TYPES
+        .iter()
+        .map(|&r#type| board.pieces(r#type).count() as i32 * PHASE_WEIGHTS[r#type as usize - 1])
+        .sum::<i32>();
+
+    // Promotions can push it past the start position
+    phase.min(MAX_PHASE)
+}
+
+// Rank counted from the color's own side
+fn relative_y(pos: Pos, color: Color) -> u8 {
+    if color == Color::White {
+        pos.y()
+    } else {
+        7 - pos.y()
+    }
+}
+
+fn ranks_ahead(y: u8, color: Color) -> Bitboard {
+    match color {
+        Color::White if y < 7 => Bitboard(u64::MAX << ((y + 1) * 8)),
+        Color::Black => Bitboard((1 << (y * 8)) - 1),
+        _ => Bitboard::EMPTY,
+    }
+}
+
+fn adjacent_files(x: u8) -> Bitboard {
+    let mut files = Bitboard::EMPTY;
+    if x > 0 {
+        files |= Bitboard::file(x - 1);
+    }
+    if x < 7 {
+        files |= Bitboard::file(x + 1);
+    }
+    files
+}
+
+// Kings are always on the board and left out
+fn material(board: &Board, color: Color) -> Score {
+    PIECE_TYPES
+        .iter()
+        .filter(|&&r#type| r#type != PieceType::King)
+        .fold(Score::default(), |score, &r#type| {
+            let value = Score(r#type.value(), r#type.endgame_value());
+            score + value * board.pieces_of(r#type, color).count() as i32
+        })
+}
+
+fn piece_squares(board: &Board, color: Color) -> Score {
+    let mut score = Score::default();
+    for pos in board.occupied_by(color) {
+        let piece = board.at_pos(pos).get_type() as usize - 1;
+        let i = (7 - relative_y(pos, color) as usize) * 8 + pos.x() as usize;
+        score += Score(tables::MIDDLEGAME[piece][i], tables::ENDGAME[piece][i]);
+    }
+
+    score
+}
+
+// Legal moves onto squares enemy pawns don't guard, the side not to move counted as if
+// the side to move had passed
+fn mobility(game: &Game) -> Score {
+    let passed = game.after_null_move();
+    let (white, black) = if game.current_color() == Color::White {
+        (game, &passed)
+    } else {
+        (&passed, game)
+    };
+
+    piece_mobility(white, Color::White) - piece_mobility(black, Color::Black)
+}
+
+fn piece_mobility(game: &Game, color: Color) -> Score {
+    let board = game.board();
+    let enemy_pawns = board.pieces_of(PieceType::Pawn, color.flip());
+    let guarded = enemy_pawns.fold(Bitboard::EMPTY, |guarded, pos| {
+        guarded | pawn_attacks(pos, color.flip())
+    });
+
+    let mut score = Score::default();
+    for &(r#type, weight, baseline) in MOBILITY.iter() {
+        for pos in board.pieces_of(r#type, color) {
+            let count = game
+                .moves_for_pos(pos)
+                .unwrap_or(&[])
+                .iter()
+                .filter(|r#move| matches!(r#move, Move::Move(to) if !guarded.contains(*to)))
+                .count() as i32;
+            score += weight * (count - baseline);
+        }
+    }
+
+    score
+}
+
+fn piece_attacks(r#type: PieceType, pos: Pos, occupied: Bitboard) -> Bitboard {
+    match r#type {
+        PieceType::Knight => knight_attacks(pos),
+        PieceType::Bishop => bishop_attacks(pos, occupied),
+        PieceType::Rook => rook_attacks(pos, occupied),
+        PieceType::Queen => queen_attacks(pos, occupied),
+        PieceType::King => king_attacks(pos),
+        PieceType::Pawn => Bitboard::EMPTY,
+    }
+}
+
+fn pawn_structure(board: &Board, color: Color) -> Score {
+    let pawns = board.pieces_of(PieceType::Pawn, color);
+    let enemy_pawns = board.pieces_of(PieceType::Pawn, color.flip());
+
+    let mut score = Score::default();
+    for x in 0..8 {
+        let count = (pawns & Bitboard::file(x)).count() as i32;
+        if count > 1 {
+            score += DOUBLED_PAWN * (count - 1);
+        }
+    }
+
+    for pos in pawns {
+        let files = adjacent_files(pos.x());
+        if (pawns & files).is_empty() {
+            score += ISOLATED_PAWN;
+        }
+
+        let front_span = (files | Bitboard::file(pos.x())) & ranks_ahead(pos.y(), color);
+        let blocked_by_own = !(pawns & Bitboard::file(pos.x()) & front_span).is_empty();
+        if (enemy_pawns & front_span).is_empty() && !blocked_by_own {
+            score += PASSED_PAWN[relative_y(pos, color) as usize];
+        }
+    }
+
+    score
+}
+
+// Pawns in front of the king, minus danger from enemy pieces hitting the squares around it
+fn king_safety(board: &Board, color: Color) -> Score {
+    let king = board.find_king(color);
+    let files = adjacent_files(king.x()) | Bitboard::file(king.x());
+    let forward = if color == Color::White { 1 } else { -1 };
+    let close_ranks = (1..=2)
+        .filter_map(|ahead| king.move_y(forward * ahead))
+        .fold(Bitboard::EMPTY, |ranks, pos| {
+            ranks | Bitboard::rank(pos.y())
+        });
+    let shield = board.pieces_of(PieceType::Pawn, color) & files & close_ranks;
+
+    let zone = king_attacks(king) | Bitboard::from_pos(king);
+    let occupied = board.occupied();
+    let mut danger = 0;
+    for pos in board.occupied_by(color.flip()) {
+        let r#type = board.at_pos(pos).get_type();
+        if !(piece_attacks(r#type, pos, occupied) & zone).is_empty() {
+            danger += KING_ATTACK_WEIGHTS[r#type as usize - 1];
+        }
+    }
+
+    PAWN_SHIELD * shield.count().min(3) as i32
+        + Score(-(2 * danger * danger).min(MAX_KING_DANGER), 0)
+}
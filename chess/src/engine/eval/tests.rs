@@ -0,0 +1,125 @@
+use super::*;
+use crate::game::START_FEN;
+
+fn breakdown(fen: &str) -> Breakdown {
+    DefaultEvaluator.breakdown(&Game::from_fen(fen).unwrap())
+}
+
+fn evaluate(fen: &str) -> i32 {
+    DefaultEvaluator.evaluate(&Game::from_fen(fen).unwrap())
+}
+
+// Same position with the colors swapped, castling and en passant dropped
+fn mirror(fen: &str) -> String {
+    let fields: Vec<&str> = fen.split(' ').collect();
+    let swap_case = |c: char| {
+        if c.is_ascii_uppercase() {
+            c.to_ascii_lowercase()
+        } else {
+            c.to_ascii_uppercase()
+        }
+    };
+
+    let ranks: Vec<String> = fields[0]
+        .split('/')
+        .rev()
+        .map(|rank| rank.chars().map(swap_case).collect())
+        .collect();
+    let player = if fields[1] == "w" { "b" } else { "w" };
+
+    format!("{} {} - - 0 1", ranks.join("/"), player)
+}
+
+#[test]
+fn start_position_is_even() {
+    let breakdown = breakdown(START_FEN);
+
+    assert_eq!(breakdown.white_score(), 0);
+    assert_eq!(breakdown.phase, MAX_PHASE);
+}
+
+#[test]
+fn symmetric_for_both_colors() {
+    let positions = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w - - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w - - 1 8",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "6k1/5ppp/8/8/8/8/PP6/6K1 b - - 0 1",
+    ];
+
+    for fen in positions.iter() {
+        assert_eq!(evaluate(fen), evaluate(&mirror(fen)), "{}", fen);
+    }
+}
+
+#[test]
+fn side_to_move_view() {
+    let white = "3qk3/8/8/8/8/8/8/3QK2Q w - - 0 1";
+    let black = "3qk3/8/8/8/8/8/8/3QK2Q b - - 0 1";
+
+    assert!(breakdown(white).material > 800);
+    assert!(evaluate(white) > 0);
+    assert_eq!(evaluate(black), -evaluate(white));
+}
+
+#[test]
+fn phase() {
+    assert_eq!(
+        breakdown("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1").phase,
+        0
+    );
+    assert_eq!(breakdown("3qk3/8/8/8/8/8/8/3QK3 w - - 0 1").phase, 8);
+}
+
+#[test]
+fn weak_pawns() {
+    // Doubled and isolated a-pawns against a healthy pair
+    assert!(breakdown("4k3/pp6/8/8/8/P7/P7/4K3 w - - 0 1").pawn_structure < 0);
+}
+
+#[test]
+fn passed_pawns() {
+    let advanced = breakdown("4k3/8/8/1P6/8/8/8/4K3 w - - 0 1");
+    let home = breakdown("4k3/8/8/8/8/8/1P6/4K3 w - - 0 1");
+    let stopped = breakdown("4k3/2p5/8/1P6/8/8/8/4K3 w - - 0 1");
+
+    assert!(advanced.pawn_structure > home.pawn_structure);
+    assert!(advanced.pawn_structure > 0);
+    assert!(stopped.pawn_structure < advanced.pawn_structure);
+}
+
+#[test]
+fn king_safety() {
+    let even = breakdown("r2q1rk1/5ppp/8/8/8/8/5PPP/R2Q1RK1 w - - 0 1");
+    let open = breakdown("r2q1rk1/8/8/8/8/8/5PPP/R2Q1RK1 w - - 0 1");
+    let attacked = breakdown("r2q1rk1/5ppp/8/8/8/5N1Q/5PPP/R4RK1 w - - 0 1");
+
+    assert_eq!(even.king_safety, 0);
+    assert!(open.king_safety > 0);
+    assert!(attacked.king_safety > 0);
+}
+
+#[test]
+fn mobility() {
+    // A knight in the corner against one in the center
+    let corner = breakdown("4k3/8/8/8/3n4/8/8/N3K3 w - - 0 1");
+    assert!(corner.mobility < 0);
+    // The side not to move is counted too
+    let other_side = breakdown("4k3/8/8/8/3n4/8/8/N3K3 b - - 0 1");
+    assert_eq!(other_side.mobility, corner.mobility);
+
+    // A pinned knight has no legal moves
+    let pinned = breakdown("4r2k/8/8/8/8/8/4N3/4K3 w - - 0 1");
+    let free = breakdown("3r3k/8/8/8/8/8/4N3/4K3 w - - 0 1");
+    assert!(pinned.mobility < free.mobility);
+}
+
+#[test]
+fn breakdown_adds_up() {
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 2 3";
+    let breakdown = breakdown(fen);
+
+    assert_eq!(breakdown.score(), evaluate(fen));
+    assert_eq!(breakdown.score(), -breakdown.white_score());
+    assert!(breakdown.to_string().contains("pawn structure"));
+}
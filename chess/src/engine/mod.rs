@@ -0,0 +1,151 @@
+use crate::{game::Game, FullMove};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod eval;
+mod picker;
+mod search;
+pub mod tt;
+
+pub use eval::{Breakdown, DefaultEvaluator, Evaluator};
+pub use tt::{Bound, TranspositionTable};
+
+#[cfg(test)]
+mod tests;
+
+// Score of being mated right now, mates further away score closer to zero
+pub const MATE: i32 = 30_000;
+pub const MAX_PLY: usize = 64;
+
+pub const DEFAULT_HASH_MB: usize = 16;
+pub const MAX_HASH_MB: usize = 4096;
+
+// Moves until mate for mate scores, negative when the side to move gets mated
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score >= MATE - MAX_PLY as i32 {
+        Some((MATE - score + 1) / 2)
+    } else if score <= -MATE + MAX_PLY as i32 {
+        Some(-(MATE + score) / 2)
+    } else {
+        None
+    }
+}
+
+// Search stops at whichever limit it hits first, with no limits it runs until stopped
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+impl Limits {
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Default::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Self {
+            time: Some(time),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SearchResult {
+    // None when the side to move has no legal moves
+    pub best_move: Option<FullMove>,
+    pub pv: Vec<FullMove>,
+    // Centipawns from the side to move's point of view
+    pub score: i32,
+    // Last fully searched depth
+    pub depth: u32,
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+pub struct Engine<E = DefaultEvaluator> {
+    stop: Arc<AtomicBool>,
+    evaluator: E,
+    tt: TranspositionTable,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self::with_evaluator(DefaultEvaluator)
+    }
+}
+
+impl<E: Evaluator> Engine<E> {
+    pub fn with_evaluator(evaluator: E) -> Self {
+        Self {
+            stop: Arc::new(AtomicBool::new(false)),
+            evaluator,
+            tt: TranspositionTable::new(DEFAULT_HASH_MB),
+        }
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    pub fn tt(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    // Throws away everything the table holds
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.tt.resize(megabytes);
+    }
+
+    // Forget earlier searches, for when a new game starts
+    pub fn clear_hash(&mut self) {
+        self.tt.clear();
+    }
+
+    // Setting the flag from any thread ends the running search, it is cleared when a
+    // search starts and when it returns
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn search(&mut self, game: &Game, limits: Limits) -> SearchResult {
+        self.search_with(game, limits, |_| {})
+    }
+
+    // Calls on_iteration with the result of every completed depth
+    pub fn search_with<F>(&mut self, game: &Game, limits: Limits, on_iteration: F) -> SearchResult
+    where
+        F: FnMut(&SearchResult),
+    {
+        self.stop.store(false, Ordering::Relaxed);
+        self.tt.new_search();
+        let result = search::Searcher::new(game, limits, &self.stop, &self.evaluator, &mut self.tt)
+            .run(on_iteration);
+        self.stop.store(false, Ordering::Relaxed);
+        result
+    }
+}
@@ -0,0 +1,313 @@
+use super::MAX_PLY;
+use crate::bitboard::{color_index, Bitboard};
+use crate::game::{Game, LegalMove};
+use crate::{Color, FullMove, Move, MoveKind, PieceType};
+
+// What the search learned about quiet moves, shared by every node of one search
+pub(super) struct History {
+    // Two quiet moves per ply that caused a cutoff in a sibling node
+    killers: [[FullMove; 2]; MAX_PLY],
+    // Quiet move that refuted each previous move, indexed by its from and to square
+    counters: Vec<[FullMove; 64]>,
+    // Cutoffs per color, from and to square, weighted by depth
+    scores: Vec<[[i32; 64]; 64]>,
+}
+
+impl History {
+    pub(super) fn new() -> Self {
+        Self {
+            killers: [[FullMove::NULL; 2]; MAX_PLY],
+            counters: vec![[FullMove::NULL; 64]; 64],
+            scores: vec![[[0; 64]; 64]; 2],
+        }
+    }
+
+    fn counter(&self, previous: FullMove) -> FullMove {
+        if previous.is_null() {
+            FullMove::NULL
+        } else {
+            self.counters[previous.origin().index()][previous.target().index()]
+        }
+    }
+
+    fn score(&self, color: Color, r#move: FullMove) -> i32 {
+        self.scores[color_index(color)][r#move.origin().index()][r#move.target().index()]
+    }
+
+    // Called when a quiet move fails high
+    pub(super) fn record_cutoff(
+        &mut self,
+        color: Color,
+        ply: usize,
+        depth: u32,
+        r#move: FullMove,
+        previous: FullMove,
+    ) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != r#move {
+            killers[1] = killers[0];
+            killers[0] = r#move;
+        }
+
+        if !previous.is_null() {
+            self.counters[previous.origin().index()][previous.target().index()] = r#move;
+        }
+
+        let score =
+            &mut self.scores[color_index(color)][r#move.origin().index()][r#move.target().index()];
+        *score += (depth * depth) as i32;
+
+        // Halve everything before it can overflow, keeping the order
+        if *score > 1 << 24 {
+            for table in self.scores.iter_mut() {
+                for from in table.iter_mut() {
+                    for score in from.iter_mut() {
+                        *score /= 2;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    GeneratePromotions,
+    Promotions,
+    Killers,
+    CounterMove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+// Hands out the moves of a position best first, one stage at a time. Each stage is only
+// collected and scored once the ones before it are used up, so a cutoff early on skips
+// the work for the rest.
+pub(super) struct MovePicker {
+    stage: Stage,
+    hash_move: FullMove,
+    killers: [FullMove; 2],
+    killer_index: usize,
+    counter: FullMove,
+    // Only captures and promotions that don't lose material
+    noisy_only: bool,
+    moves: Vec<(i32, FullMove)>,
+    bad_captures: Vec<(i32, FullMove)>,
+    // Hash move, killers and counter move, already handed out before the quiets
+    special: [FullMove; 4],
+}
+
+fn is_legal(game: &Game, r#move: FullMove) -> bool {
+    let (from, r#move) = r#move.to_pair();
+    game.is_legal(from, r#move)
+}
+
+pub(super) fn is_quiet(game: &Game, r#move: FullMove) -> bool {
+    match r#move.kind() {
+        MoveKind::Promotion | MoveKind::EnPassant => false,
+        MoveKind::Castling => true,
+        MoveKind::Normal => game.at_pos(r#move.target()).is_empty(),
+    }
+}
+
+// Biggest victim first, then the cheapest attacker
+fn mvv_lva(game: &Game, r#move: &LegalMove) -> i32 {
+    let victim = match r#move.r#move {
+        Move::EnPassant(_) => PieceType::Pawn,
+        _ => game.at_pos(r#move.to).get_type(),
+    };
+
+    victim.value() * 32 - r#move.piece.value() / 100
+}
+
+impl MovePicker {
+    pub(super) fn new(
+        game: &Game,
+        history: &History,
+        ply: usize,
+        hash_move: FullMove,
+        previous: FullMove,
+    ) -> Self {
+        Self {
+            stage: Stage::HashMove,
+            hash_move,
+            killers: history.killers[ply],
+            killer_index: 0,
+            counter: history.counter(previous),
+            noisy_only: false,
+            moves: Vec::new(),
+            bad_captures: Vec::new(),
+            special: [FullMove::NULL; 4],
+        }
+        .with_hash_move(game)
+    }
+
+    // For quiescence search, winning and even captures and promotions only
+    pub(super) fn noisy() -> Self {
+        Self {
+            stage: Stage::GenerateCaptures,
+            hash_move: FullMove::NULL,
+            killers: [FullMove::NULL; 2],
+            killer_index: 0,
+            counter: FullMove::NULL,
+            noisy_only: true,
+            moves: Vec::new(),
+            bad_captures: Vec::new(),
+            special: [FullMove::NULL; 4],
+        }
+    }
+
+    fn with_hash_move(mut self, game: &Game) -> Self {
+        if self.hash_move.is_null() || !is_legal(game, self.hash_move) {
+            self.hash_move = FullMove::NULL;
+            self.stage = Stage::GenerateCaptures;
+        }
+
+        self.special[0] = self.hash_move;
+        self
+    }
+
+    fn already_picked(&self, r#move: FullMove) -> bool {
+        self.special.contains(&r#move)
+    }
+
+    // Takes the best scored move left in the current stage
+    fn pick_best(&mut self) -> Option<FullMove> {
+        let best = (0..self.moves.len()).max_by_key(|&i| self.moves[i].0)?;
+        Some(self.moves.swap_remove(best).1)
+    }
+
+    pub(super) fn next(&mut self, game: &Game, history: &History) -> Option<FullMove> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    return Some(self.hash_move);
+                }
+                Stage::GenerateCaptures => {
+                    let board = game.board();
+                    let mut targets = board.occupied_by(game.current_color().flip());
+                    if let Some(to) = game.en_passant() {
+                        targets.insert(to);
+                    }
+
+                    for r#move in game.moves_onto(targets) {
+                        let full_move = FullMove::from(r#move);
+                        if !game.is_capture(&r#move) || self.already_picked(full_move) {
+                            continue;
+                        }
+
+                        let mut score = mvv_lva(game, &r#move);
+                        if let Some(r#type) = r#move.promotion() {
+                            score += r#type.value() * 32;
+                        }
+
+                        // Taking something worth at least the attacker can't lose material
+                        let victim = game.at_pos(r#move.to);
+                        let good = victim.is_empty()
+                            || victim.get_type().value() >= r#move.piece.value()
+                            || board.see(r#move.from, r#move.to) >= 0;
+
+                        if good {
+                            self.moves.push((score, full_move));
+                        } else {
+                            self.bad_captures.push((score, full_move));
+                        }
+                    }
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => match self.pick_best() {
+                    Some(r#move) => return Some(r#move),
+                    None => self.stage = Stage::GeneratePromotions,
+                },
+                Stage::GeneratePromotions => {
+                    let y = if game.current_color() == Color::White {
+                        7
+                    } else {
+                        0
+                    };
+                    let targets = Bitboard::rank(y) & !game.board().occupied();
+
+                    for r#move in game.moves_onto(targets) {
+                        let full_move = FullMove::from(r#move);
+                        if game.is_capture(&r#move) || self.already_picked(full_move) {
+                            continue;
+                        }
+
+                        if let Some(r#type) = r#move.promotion() {
+                            self.moves.push((r#type.value(), full_move));
+                        }
+                    }
+                    self.stage = Stage::Promotions;
+                }
+                Stage::Promotions => match self.pick_best() {
+                    Some(r#move) => return Some(r#move),
+                    None if self.noisy_only => self.stage = Stage::Done,
+                    None => self.stage = Stage::Killers,
+                },
+                Stage::Killers => {
+                    while self.killer_index < 2 {
+                        let killer = self.killers[self.killer_index];
+                        self.killer_index += 1;
+
+                        if !killer.is_null()
+                            && !self.already_picked(killer)
+                            && is_quiet(game, killer)
+                            && is_legal(game, killer)
+                        {
+                            self.special[self.killer_index] = killer;
+                            return Some(killer);
+                        }
+                    }
+                    self.stage = Stage::CounterMove;
+                }
+                Stage::CounterMove => {
+                    self.stage = Stage::GenerateQuiets;
+                    let counter = self.counter;
+                    if !counter.is_null()
+                        && !self.already_picked(counter)
+                        && is_quiet(game, counter)
+                        && is_legal(game, counter)
+                    {
+                        self.special[3] = counter;
+                        return Some(counter);
+                    }
+                }
+                // Only here is the full move map built
+                Stage::GenerateQuiets => {
+                    let color = game.current_color();
+                    for r#move in game.legal_moves() {
+                        let full_move = FullMove::from(r#move);
+                        if game.is_capture(&r#move)
+                            || r#move.promotion().is_some()
+                            || self.already_picked(full_move)
+                        {
+                            continue;
+                        }
+
+                        self.moves
+                            .push((history.score(color, full_move), full_move));
+                    }
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match self.pick_best() {
+                    Some(r#move) => return Some(r#move),
+                    None => {
+                        self.moves = std::mem::take(&mut self.bad_captures);
+                        self.stage = Stage::BadCaptures;
+                    }
+                },
+                Stage::BadCaptures => match self.pick_best() {
+                    Some(r#move) => return Some(r#move),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use super::picker::{is_quiet, History, MovePicker};
+use super::tt::{Bound, TranspositionTable};
+use super::{Evaluator, Limits, SearchResult, MATE, MAX_PLY};
+use crate::{game::Game, FullMove};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const INFINITY: i32 = MATE + 1;
+
+// How often the clock and the stop flag are looked at, a power of two
+const CHECK_INTERVAL: u64 = 1024;
+
+pub(super) struct Searcher<'a, E> {
+    game: Game,
+    evaluator: &'a E,
+    tt: &'a mut TranspositionTable,
+    limits: Limits,
+    stop: &'a AtomicBool,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+    // Triangular table, pv[ply] holds the best line found from ply onwards
+    pv: Vec<[FullMove; MAX_PLY]>,
+    pv_len: [usize; MAX_PLY + 1],
+    // Best line of the previous iteration, tried first while the search is still on it
+    prev_pv: Vec<FullMove>,
+    follow_pv: bool,
+    history: History,
+    // Move made at each ply of the current line
+    played: [FullMove; MAX_PLY],
+}
+
+impl<'a, E: Evaluator> Searcher<'a, E> {
+    pub(super) fn new(
+        game: &Game,
+        limits: Limits,
+        stop: &'a AtomicBool,
+        evaluator: &'a E,
+        tt: &'a mut TranspositionTable,
+    ) -> Self {
+        Self {
+            game: game.clone(),
+            evaluator,
+            tt,
+            limits,
+            stop,
+            start: Instant::now(),
+            nodes: 0,
+            stopped: false,
+            pv: vec![[FullMove::NULL; MAX_PLY]; MAX_PLY],
+            pv_len: [0; MAX_PLY + 1],
+            prev_pv: Vec::new(),
+            follow_pv: false,
+            history: History::new(),
+            played: [FullMove::NULL; MAX_PLY],
+        }
+    }
+
+    pub(super) fn run<F: FnMut(&SearchResult)>(mut self, mut on_iteration: F) -> SearchResult {
+        let mut result = SearchResult {
+            best_move: self.game.legal_moves().next().map(FullMove::from),
+            pv: Vec::new(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            time: Duration::from_secs(0),
+        };
+
+        if result.best_move.is_none() {
+            result.score = if self.game.is_king_in_danger() {
+                -MATE
+            } else {
+                0
+            };
+            return result;
+        }
+
+        let max_depth = self
+            .limits
+            .depth
+            .unwrap_or(MAX_PLY as u32)
+            .min(MAX_PLY as u32 - 1);
+
+        for depth in 1..=max_depth {
+            self.follow_pv = true;
+            let score = self.negamax(depth, 0, -INFINITY, INFINITY);
+
+            // A cut short iteration can't be trusted, keep the last complete one
+            if self.stopped {
+                break;
+            }
+
+            self.prev_pv = self.pv[0][..self.pv_len[0]].to_vec();
+            result = SearchResult {
+                best_move: self.prev_pv.first().copied(),
+                pv: self.prev_pv.clone(),
+                score,
+                depth,
+                nodes: self.nodes,
+                time: self.start.elapsed(),
+            };
+            on_iteration(&result);
+
+            // The next iteration takes longer than all before it, so don't start what
+            // can't be finished
+            if let Some(time) = self.limits.time {
+                if self.start.elapsed() * 2 > time {
+                    break;
+                }
+            }
+
+            // A mate within the searched depth can't get any shorter
+            if MATE - score.abs() <= depth as i32 {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result.time = self.start.elapsed();
+        result
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        if let Some(nodes) = self.limits.nodes {
+            if self.nodes >= nodes {
+                self.stopped = true;
+            }
+        }
+
+        if self.nodes & (CHECK_INTERVAL - 1) == 0 {
+            if self.stop.load(Ordering::Relaxed) {
+                self.stopped = true;
+            }
+
+            if let Some(time) = self.limits.time {
+                if self.start.elapsed() >= time {
+                    self.stopped = true;
+                }
+            }
+        }
+
+        self.stopped
+    }
+
+    // Repetitions inside the search count as draws right away, that is enough to avoid them
+    fn is_draw(&self) -> bool {
+        self.game.repetitions() >= 2
+            || self.game.halfmove_clock() >= 100
+            || self.game.board().has_insufficient_material()
+    }
+
+    // The previous iteration's line comes first while the search is still on it, then
+    // the move the table remembers
+    fn hash_move(&mut self, ply: usize, tt_move: FullMove) -> FullMove {
+        if self.follow_pv {
+            match self.prev_pv.get(ply) {
+                Some(&pv_move) => return pv_move,
+                None => self.follow_pv = false,
+            }
+        }
+
+        tt_move
+    }
+
+    fn make_move(&mut self, ply: usize, r#move: FullMove) {
+        let (from, pair) = r#move.to_pair();
+        self.game.make_move(from, pair);
+        self.played[ply] = r#move;
+    }
+
+    fn previous_move(&self, ply: usize) -> FullMove {
+        if ply > 0 {
+            self.played[ply - 1]
+        } else {
+            FullMove::NULL
+        }
+    }
+
+    fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_len[ply] = ply;
+
+        if ply > 0 && self.is_draw() {
+            return 0;
+        }
+
+        if !self.game.has_legal_moves() {
+            return if self.game.is_king_in_danger() {
+                -MATE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let key = self.game.zobrist();
+        let mut tt_move = FullMove::NULL;
+        if let Some(entry) = self.tt.probe(key, ply) {
+            tt_move = entry.best_move;
+
+            // The root always searches so it has a line to return
+            if ply > 0 && entry.depth >= depth {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+
+                if cutoff {
+                    return entry.score;
+                }
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = FullMove::NULL;
+
+        let hash_move = self.hash_move(ply, tt_move);
+        let previous = self.previous_move(ply);
+        let mut picker = MovePicker::new(&self.game, &self.history, ply, hash_move, previous);
+        let mut first = true;
+
+        while let Some(r#move) = picker.next(&self.game, &self.history) {
+            let quiet = is_quiet(&self.game, r#move);
+            self.make_move(ply, r#move);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            self.game.unmake_move();
+
+            if first {
+                self.follow_pv = false;
+                first = false;
+            }
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = r#move;
+            }
+
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, r#move);
+
+                if alpha >= beta {
+                    if quiet {
+                        let color = self.game.current_color();
+                        self.history
+                            .record_cutoff(color, ply, depth, r#move, previous);
+                    }
+                    break;
+                }
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt.store(key, depth, bound, best_score, best_move, ply);
+
+        best_score
+    }
+
+    // Keeps searching noisy moves past the depth limit so the evaluation isn't taken in the
+    // middle of an exchange
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_len[ply] = ply;
+
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply >= MAX_PLY - 1 {
+            return self.evaluator.evaluate(&self.game);
+        }
+
+        // In check there is no standing pat, every evasion gets searched
+        let in_check = self.game.is_king_in_danger();
+        let mut best_score = -MATE + ply as i32;
+        let mut picker = if in_check {
+            MovePicker::new(
+                &self.game,
+                &self.history,
+                ply,
+                FullMove::NULL,
+                FullMove::NULL,
+            )
+        } else {
+            let stand_pat = self.evaluator.evaluate(&self.game);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+
+            alpha = alpha.max(stand_pat);
+            best_score = stand_pat;
+            MovePicker::noisy()
+        };
+
+        while let Some(r#move) = picker.next(&self.game, &self.history) {
+            self.make_move(ply, r#move);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.game.unmake_move();
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+
+                if score > alpha {
+                    alpha = score;
+
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+
+        best_score
+    }
+
+    fn update_pv(&mut self, ply: usize, r#move: FullMove) {
+        let len = self.pv_len[ply + 1].max(ply + 1);
+        self.pv[ply][ply] = r#move;
+        for i in ply + 1..len {
+            self.pv[ply][i] = self.pv[ply + 1][i];
+        }
+        self.pv_len[ply] = len;
+    }
+}
@@ -0,0 +1,264 @@
+use super::*;
+use crate::game::GameResult;
+use crate::{Color, Pos};
+
+use std::str::FromStr;
+use std::time::Instant;
+
+fn search(fen: &str, limits: Limits) -> SearchResult {
+    Engine::new().search(&Game::from_fen(fen).unwrap(), limits)
+}
+
+fn full_move(from: &str, to: &str) -> FullMove {
+    let from = Pos::from_str(from).unwrap();
+    let r#move = crate::Move::Move(Pos::from_str(to).unwrap());
+    FullMove::from_pair(from, r#move)
+}
+
+#[test]
+fn finds_mate_in_one() {
+    let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", Limits::depth(3));
+
+    assert_eq!(result.best_move, Some(full_move("a1", "a8")));
+    assert_eq!(result.score, MATE - 1);
+    assert_eq!(mate_in(result.score), Some(1));
+}
+
+#[test]
+fn finds_mate_in_two() {
+    let result = search("k7/8/2K5/8/8/8/8/7R w - - 0 1", Limits::depth(4));
+    assert_eq!(mate_in(result.score), Some(2));
+
+    let mut game = Game::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+    let mut status = GameResult::Ok;
+    for r#move in result.pv.iter() {
+        let (from, r#move) = r#move.to_pair();
+        status = game.play(from, r#move);
+        assert_ne!(status, GameResult::InvalidMove);
+    }
+
+    assert_eq!(result.pv.len(), 3);
+    assert_eq!(status, GameResult::Checkmate);
+}
+
+#[test]
+fn sees_being_mated() {
+    let result = search("k7/2K5/8/8/8/8/8/1R6 b - - 0 1", Limits::depth(3));
+
+    assert_eq!(result.best_move, Some(full_move("a8", "a7")));
+    assert_eq!(mate_in(result.score), Some(-1));
+}
+
+#[test]
+fn wins_material() {
+    let result = search("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", Limits::depth(2));
+
+    assert_eq!(result.best_move, Some(full_move("d1", "d5")));
+    assert!(result.score > 0);
+}
+
+#[test]
+fn no_legal_moves() {
+    let mated = search("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", Limits::depth(3));
+    assert_eq!(mated.best_move, None);
+    assert_eq!(mated.score, -MATE);
+
+    let stalemate = search("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", Limits::depth(3));
+    assert_eq!(stalemate.best_move, None);
+    assert_eq!(stalemate.score, 0);
+}
+
+#[test]
+fn depth_limit() {
+    let mut depths = Vec::new();
+    let result = Engine::new().search_with(&Game::default(), Limits::depth(3), |result| {
+        depths.push(result.depth)
+    });
+
+    assert_eq!(depths, [1, 2, 3]);
+    assert_eq!(result.depth, 3);
+    assert_eq!(result.pv.first().copied(), result.best_move);
+}
+
+#[test]
+fn node_limit() {
+    let result = search(crate::game::START_FEN, Limits::nodes(2000));
+
+    assert!(result.nodes <= 2000);
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn time_limit() {
+    let start = Instant::now();
+    let result = search(
+        crate::game::START_FEN,
+        Limits::time(Duration::from_millis(100)),
+    );
+
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn stop_from_other_thread() {
+    let mut engine = Engine::new();
+    let stop = engine.stop_flag();
+
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        stop.store(true, Ordering::Relaxed);
+    });
+
+    let result = engine.search(&Game::default(), Limits::default());
+    stopper.join().unwrap();
+
+    assert!(result.best_move.is_some());
+    assert!(!engine.stop_flag().load(Ordering::Relaxed));
+}
+
+#[test]
+fn stop_while_idle() {
+    let mut engine = Engine::new();
+    engine.stop();
+
+    let result = engine.search(&Game::default(), Limits::depth(3));
+    assert_eq!(result.depth, 3);
+}
+
+struct MaterialOnly;
+
+impl Evaluator for MaterialOnly {
+    fn evaluate(&self, game: &Game) -> i32 {
+        let board = game.board();
+        let player = game.current_color();
+        let queens = board.pieces_of(crate::PieceType::Queen, player).count() as i32;
+        let enemy_queens = board
+            .pieces_of(crate::PieceType::Queen, player.flip())
+            .count() as i32;
+
+        (queens - enemy_queens) * 900
+    }
+}
+
+#[test]
+fn custom_evaluator() {
+    let mut engine = Engine::with_evaluator(MaterialOnly);
+    let game = Game::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+    let result = engine.search(&game, Limits::depth(2));
+
+    assert_eq!(result.best_move, Some(full_move("d1", "d5")));
+    assert_eq!(result.score, 0);
+}
+
+#[test]
+fn reuses_transposition_table() {
+    let mut engine = Engine::new();
+    let game =
+        Game::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+    let first = engine.search(&game, Limits::depth(4));
+    let second = engine.search(&game, Limits::depth(4));
+    assert!(second.nodes < first.nodes);
+    assert_eq!(second.best_move, first.best_move);
+    assert!(engine.tt().probe(game.zobrist(), 0).is_some());
+
+    engine.clear_hash();
+    assert!(engine.tt().probe(game.zobrist(), 0).is_none());
+    assert_eq!(engine.search(&game, Limits::depth(4)).nodes, first.nodes);
+}
+
+#[test]
+fn quiescence_sees_recaptures() {
+    // At depth 1 the queen would take a defended pawn without quiescence
+    let result = search("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", Limits::depth(1));
+    assert_ne!(result.best_move, Some(full_move("d1", "d5")));
+
+    // And a free one is still taken
+    let result = search("4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1", Limits::depth(1));
+    assert_eq!(result.best_move, Some(full_move("d1", "d5")));
+}
+
+fn picked_moves(
+    game: &Game,
+    mut picker: picker::MovePicker,
+    history: &picker::History,
+) -> Vec<FullMove> {
+    std::iter::from_fn(|| picker.next(game, history)).collect()
+}
+
+#[test]
+fn picker_yields_every_move_once() {
+    let game =
+        Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let history = picker::History::new();
+    let hash_move =
+        FullMove::from_pair(Pos::from_str("e1").unwrap(), crate::Move::KingSideCastling);
+
+    let picker = picker::MovePicker::new(&game, &history, 0, hash_move, FullMove::NULL);
+    let moves = picked_moves(&game, picker, &history);
+
+    let mut legal: Vec<u16> = game
+        .legal_moves()
+        .map(|m| FullMove::from(m).bits())
+        .collect();
+    let mut picked: Vec<u16> = moves.iter().map(|m| m.bits()).collect();
+    legal.sort_unstable();
+    picked.sort_unstable();
+    assert_eq!(picked, legal);
+
+    assert_eq!(moves[0], hash_move);
+    // Winning captures come right after, biggest victim first
+    assert_eq!(moves[1], full_move("e2", "a6"));
+    // Losing captures go last
+    assert!(!picker::is_quiet(&game, *moves.last().unwrap()));
+}
+
+#[test]
+fn picker_killers_and_history() {
+    let game = Game::default();
+    let mut history = picker::History::new();
+    let killer = full_move("g1", "f3");
+    let good_quiet = full_move("e2", "e4");
+
+    history.record_cutoff(Color::White, 2, 1, killer, FullMove::NULL);
+    history.record_cutoff(Color::White, 5, 8, good_quiet, FullMove::NULL);
+
+    let picker = picker::MovePicker::new(&game, &history, 2, FullMove::NULL, FullMove::NULL);
+    let moves = picked_moves(&game, picker, &history);
+
+    assert_eq!(moves.len(), 20);
+    assert_eq!(moves[0], killer);
+    assert_eq!(moves[1], good_quiet);
+}
+
+#[test]
+fn picker_counter_moves() {
+    let game =
+        Game::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap();
+    let mut history = picker::History::new();
+    let previous = full_move("e7", "e5");
+    let counter = full_move("g1", "f3");
+
+    history.record_cutoff(Color::White, 7, 1, counter, previous);
+
+    let picker = picker::MovePicker::new(&game, &history, 0, FullMove::NULL, previous);
+    assert_eq!(picked_moves(&game, picker, &history)[0], counter);
+}
+
+#[test]
+fn noisy_picker_skips_losing_captures() {
+    // Qxd5 loses the queen to exd5, Nxg4 wins a pawn
+    let game = Game::from_fen("4k3/8/4p3/3p4/6p1/8/3Q1N2/4K3 w - - 0 1").unwrap();
+    let history = picker::History::new();
+
+    let noisy = picked_moves(&game, picker::MovePicker::noisy(), &history);
+    assert_eq!(noisy, [full_move("f2", "g4")]);
+
+    let all = picker::MovePicker::new(&game, &history, 0, FullMove::NULL, FullMove::NULL);
+    assert_eq!(
+        picked_moves(&game, all, &history).last().copied(),
+        Some(full_move("d2", "d5"))
+    );
+}
@@ -0,0 +1,203 @@
+use super::{MATE, MAX_HASH_MB, MAX_PLY};
+use crate::FullMove;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum Bound {
+    // The score is exact
+    Exact = 1,
+    // The search failed high, the score is at least this
+    Lower = 2,
+    // The search failed low, the score is at most this
+    Upper = 3,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Entry {
+    pub depth: u32,
+    pub bound: Bound,
+    // Mate scores count from the position being probed
+    pub score: i32,
+    pub best_move: FullMove,
+    pub age: u8,
+}
+
+// 16 bytes, bound in the low 2 bits of flags and age in the rest. A zero flags byte
+// marks an empty slot.
+#[derive(Copy, Clone, Default)]
+struct Slot {
+    key: u64,
+    best_move: FullMove,
+    score: i16,
+    depth: u8,
+    flags: u8,
+}
+
+impl Slot {
+    fn is_empty(&self) -> bool {
+        self.flags == 0
+    }
+
+    fn bound(&self) -> Bound {
+        match self.flags & 3 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            _ => Bound::Upper,
+        }
+    }
+
+    fn age(&self) -> u8 {
+        self.flags >> 2
+    }
+}
+
+// The first slot keeps the deepest search of the current age, the second takes whatever
+// the first one turned down
+#[derive(Copy, Clone, Default)]
+struct Bucket {
+    depth_preferred: Slot,
+    always_replace: Slot,
+}
+
+const AGE_MASK: u8 = 63;
+
+// Mate scores are stored relative to the node instead of the root, so they stay right
+// when the position is reached at another ply
+fn score_to_tt(score: i32, ply: usize) -> i16 {
+    let score = if score >= MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    };
+
+    score as i16
+}
+
+fn score_from_tt(score: i16, ply: usize) -> i32 {
+    let score = score as i32;
+    if score >= MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// Rounded down to a power of two, at least one and at most MAX_HASH_MB worth
+fn bucket_count(megabytes: usize) -> usize {
+    let bytes = megabytes.min(MAX_HASH_MB) * 1024 * 1024;
+    let count = (bytes / std::mem::size_of::<Bucket>()).max(1);
+    1 << (usize::BITS - 1 - count.leading_zeros())
+}
+
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: u8,
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> Self {
+        Self {
+            buckets: vec![Bucket::default(); bucket_count(megabytes)],
+            age: 0,
+        }
+    }
+
+    pub fn resize(&mut self, megabytes: usize) {
+        *self = Self::new(megabytes);
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.buckets.len() * std::mem::size_of::<Bucket>()
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            *bucket = Bucket::default();
+        }
+        self.age = 0;
+    }
+
+    // Called once per search so entries from older searches get replaced first
+    pub fn new_search(&mut self) {
+        self.age = (self.age + 1) & AGE_MASK;
+    }
+
+    fn bucket(&self, key: u64) -> usize {
+        key as usize & (self.buckets.len() - 1)
+    }
+
+    pub fn probe(&self, key: u64, ply: usize) -> Option<Entry> {
+        let bucket = &self.buckets[self.bucket(key)];
+
+        [bucket.depth_preferred, bucket.always_replace]
+            .iter()
+            .find(|slot| !slot.is_empty() && slot.key == key)
+            .map(|slot| Entry {
+                depth: slot.depth as u32,
+                bound: slot.bound(),
+                score: score_from_tt(slot.score, ply),
+                best_move: slot.best_move,
+                age: slot.age(),
+            })
+    }
+
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        best_move: FullMove,
+        ply: usize,
+    ) {
+        let age = self.age;
+        let i = self.bucket(key);
+        let bucket = &mut self.buckets[i];
+
+        let mut slot = Slot {
+            key,
+            best_move,
+            score: score_to_tt(score, ply),
+            depth: depth.min(u8::MAX as u32) as u8,
+            flags: (age << 2) | bound as u8,
+        };
+
+        let preferred = &bucket.depth_preferred;
+        let takes_preferred = preferred.is_empty()
+            || preferred.key == key
+            || preferred.age() != age
+            || slot.depth >= preferred.depth;
+
+        let target = if takes_preferred {
+            &mut bucket.depth_preferred
+        } else {
+            &mut bucket.always_replace
+        };
+
+        // A search that found no best move shouldn't forget the one already known
+        if best_move.is_null() && target.key == key {
+            slot.best_move = target.best_move;
+        }
+
+        *target = slot;
+    }
+
+    // How full the table is with entries of the current search, in permille
+    pub fn hashfull(&self) -> usize {
+        let sample = self.buckets.len().min(500);
+        let used = self.buckets[..sample]
+            .iter()
+            .flat_map(|bucket| [bucket.depth_preferred, bucket.always_replace])
+            .filter(|slot| !slot.is_empty() && slot.age() == self.age)
+            .count();
+
+        used * 1000 / (sample * 2)
+    }
+}
@@ -0,0 +1,108 @@
+use super::*;
+use crate::engine::MAX_HASH_MB;
+use crate::Pos;
+
+fn full_move(from: u8, to: u8) -> FullMove {
+    FullMove::new(
+        Pos::new_index(from),
+        Pos::new_index(to),
+        crate::MoveKind::Normal,
+    )
+}
+
+#[test]
+fn store_and_probe() {
+    let mut tt = TranspositionTable::new(1);
+    let key = 0x1234_5678_9ABC_DEF0;
+
+    assert_eq!(tt.probe(key, 0), None);
+
+    tt.store(key, 5, Bound::Lower, 42, full_move(12, 28), 0);
+    let entry = tt.probe(key, 0).unwrap();
+    assert_eq!(entry.depth, 5);
+    assert_eq!(entry.bound, Bound::Lower);
+    assert_eq!(entry.score, 42);
+    assert_eq!(entry.best_move, full_move(12, 28));
+
+    // Same bucket, different position
+    assert_eq!(tt.probe(key ^ (1 << 63), 0), None);
+}
+
+#[test]
+fn size_in_megabytes() {
+    let tt = TranspositionTable::new(16);
+    assert_eq!(tt.size_bytes(), 16 * 1024 * 1024);
+
+    let tt = TranspositionTable::new(3);
+    assert_eq!(tt.size_bytes(), 2 * 1024 * 1024);
+
+    assert_eq!(TranspositionTable::new(0).buckets.len(), 1);
+    assert_eq!(bucket_count(usize::MAX), bucket_count(MAX_HASH_MB));
+}
+
+#[test]
+fn mate_scores_follow_ply() {
+    let mut tt = TranspositionTable::new(1);
+
+    // Mate found 3 plies below a node at ply 4, probed again at ply 2
+    tt.store(1, 3, Bound::Exact, MATE - 7, FullMove::NULL, 4);
+    assert_eq!(tt.probe(1, 2).unwrap().score, MATE - 5);
+
+    tt.store(2, 3, Bound::Exact, -MATE + 8, FullMove::NULL, 4);
+    assert_eq!(tt.probe(2, 6).unwrap().score, -MATE + 10);
+
+    tt.store(3, 3, Bound::Exact, -250, FullMove::NULL, 4);
+    assert_eq!(tt.probe(3, 9).unwrap().score, -250);
+}
+
+#[test]
+fn replacement() {
+    let mut tt = TranspositionTable::new(0);
+    let (deep, shallow, newer) = (1, 2, 3);
+
+    tt.store(deep, 8, Bound::Exact, 0, full_move(1, 2), 0);
+    tt.store(shallow, 2, Bound::Exact, 0, full_move(3, 4), 0);
+    assert!(tt.probe(deep, 0).is_some());
+    assert!(tt.probe(shallow, 0).is_some());
+
+    // The always replace slot takes the next shallow entry
+    tt.store(newer, 1, Bound::Exact, 0, full_move(5, 6), 0);
+    assert!(tt.probe(deep, 0).is_some());
+    assert!(tt.probe(shallow, 0).is_none());
+    assert!(tt.probe(newer, 0).is_some());
+
+    // Deep entries from an earlier search give way
+    tt.new_search();
+    tt.store(shallow, 1, Bound::Exact, 0, full_move(3, 4), 0);
+    assert!(tt.probe(deep, 0).is_none());
+    assert_eq!(tt.probe(shallow, 0).unwrap().age, 1);
+}
+
+#[test]
+fn keeps_best_move() {
+    let mut tt = TranspositionTable::new(1);
+
+    tt.store(7, 4, Bound::Lower, 10, full_move(8, 16), 0);
+    tt.store(7, 5, Bound::Upper, -10, FullMove::NULL, 0);
+
+    let entry = tt.probe(7, 0).unwrap();
+    assert_eq!(entry.bound, Bound::Upper);
+    assert_eq!(entry.best_move, full_move(8, 16));
+}
+
+#[test]
+fn clear_and_hashfull() {
+    let mut tt = TranspositionTable::new(1);
+    assert_eq!(tt.hashfull(), 0);
+
+    for key in 0..1000 {
+        tt.store(key, 1, Bound::Exact, 0, FullMove::NULL, 0);
+    }
+    assert!(tt.hashfull() >= 500);
+
+    tt.new_search();
+    assert_eq!(tt.hashfull(), 0);
+
+    tt.clear();
+    assert_eq!(tt.probe(5, 0), None);
+}
@@ -0,0 +1,48 @@
+use crate::game::Game;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum DrawReason {
+    InsufficientMaterial,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+}
+
+impl Game {
+    // Number of times the current position has occurred, including now
+    pub fn repetitions(&self) -> usize {
+        let current = self.zobrist;
+        let reversible = std::cmp::min(self.halfmove_clock as usize, self.positions.len() - 1);
+
+        self.positions
+            .iter()
+            .rev()
+            .take(reversible + 1)
+            .step_by(2)
+            .filter(|&&key| key == current)
+            .count()
+    }
+
+    pub fn automatic_draw(&self) -> Option<DrawReason> {
+        if self.board.has_insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else if self.repetitions() >= 5 {
+            Some(DrawReason::FivefoldRepetition)
+        } else if self.halfmove_clock >= 150 {
+            Some(DrawReason::SeventyFiveMoveRule)
+        } else {
+            None
+        }
+    }
+
+    pub fn can_claim_draw(&self) -> Option<DrawReason> {
+        if self.repetitions() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{board::ParseFenError, game::Game, Board, Color, PieceType, Pos, TaggedPiece};
+
+use std::cell::OnceCell;
+use std::str::FromStr;
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+impl Game {
+    pub fn from_fen(fen: &str) -> Result<Self, ParseFenError> {
+        let board = Board::from_fen(fen)?;
+
+        let mut fields = fen.split_whitespace().skip(1);
+
+        let player = match fields.next() {
+            Some("w") => Color::White,
+            Some("b") => Color::Black,
+            Some(_) => return Err(ParseFenError::InvalidColor),
+            None => return Err(ParseFenError::MissingField),
+        };
+
+        // The castling field is already applied by Board::from_fen
+        fields.next();
+
+        let en_passant = match fields.next() {
+            Some("-") | None => None,
+            Some(s) => {
+                let pos = Pos::from_str(s).map_err(|_| ParseFenError::InvalidEnPassant)?;
+                let rank = if player == Color::White { 5 } else { 2 };
+                if pos.y() != rank {
+                    return Err(ParseFenError::InvalidEnPassant);
+                }
+                Some(pos)
+            }
+        };
+
+        let halfmove_clock = match fields.next() {
+            Some(s) => u32::from_str(s).map_err(|_| ParseFenError::InvalidClock)?,
+            None => 0,
+        };
+
+        let fullmove_number = match fields.next() {
+            Some(s) => u32::from_str(s).map_err(|_| ParseFenError::InvalidClock)?,
+            None => 1,
+        };
+
+        if fullmove_number == 0 {
+            return Err(ParseFenError::InvalidClock);
+        }
+
+        for color in [Color::White, Color::Black].iter() {
+            let king = TaggedPiece::new(PieceType::King, *color);
+            let kings = (0..64).filter(|&i| board.at_index(i) == king).count();
+            if kings != 1 {
+                return Err(ParseFenError::InvalidKings);
+            }
+        }
+
+        let mut game = Self {
+            board,
+            move_map: OnceCell::new(),
+            history: Vec::new(),
+            positions: Vec::new(),
+            player,
+            king_pos: board.find_king(player),
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            start_fen: String::new(),
+            zobrist: 0,
+            chess960: board.has_chess960_castling(),
+        };
+
+        game.start_fen = game.to_fen();
+        game.zobrist = game.calculate_zobrist();
+        game.positions.push(game.zobrist);
+
+        Ok(game)
+    }
+
+    // Castling rights in X-FEN, the same as regular FEN for standard positions
+    pub fn to_fen(&self) -> String {
+        self.format_fen(self.board.castling_fen())
+    }
+
+    // Castling rights named by rook file, as some Chess960 GUIs expect
+    pub fn to_shredder_fen(&self) -> String {
+        self.format_fen(self.board.shredder_castling_fen())
+    }
+
+    fn format_fen(&self, castling: String) -> String {
+        let en_passant = match self.en_passant {
+            Some(pos) => pos.to_string(),
+            None => "-".to_string(),
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            self.board.to_fen(),
+            if self.player == Color::White {
+                "w"
+            } else {
+                "b"
+            },
+            castling,
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
@@ -0,0 +1,155 @@
+use crate::bitboard::Bitboard;
+use crate::{game::Game, Color, FullMove, Move, PieceType, Pos};
+
+// A legal move of the side to move, with both squares spelled out
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LegalMove {
+    pub from: Pos,
+    pub to: Pos,
+    pub piece: PieceType,
+    pub r#move: Move,
+}
+
+impl LegalMove {
+    pub(super) fn new(from: Pos, r#move: Move, piece: PieceType, color: Color) -> Self {
+        let y = if color == Color::White { 0 } else { 7 };
+        let to = match r#move {
+            Move::Move(to) | Move::EnPassant(to) | Move::PawnPromotion(_, to) => to,
+            Move::KingSideCastling => Pos::new_xy(6, y),
+            Move::QueenSideCastling => Pos::new_xy(2, y),
+            Move::None => from,
+        };
+
+        LegalMove {
+            from,
+            to,
+            piece,
+            r#move,
+        }
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        match self.r#move {
+            Move::PawnPromotion(r#type, _) => Some(r#type),
+            _ => None,
+        }
+    }
+}
+
+impl From<LegalMove> for FullMove {
+    fn from(r#move: LegalMove) -> Self {
+        FullMove::from_pair(r#move.from, r#move.r#move)
+    }
+}
+
+impl Game {
+    pub fn legal_moves(&self) -> impl Iterator<Item = LegalMove> + '_ {
+        self.board.occupied_by(self.player).flat_map(move |from| {
+            let piece = self.at_pos(from).get_type();
+            self.moves_for_pos(from)
+                .unwrap_or(&[])
+                .iter()
+                .map(move |&r#move| LegalMove::new(from, r#move, piece, self.player))
+        })
+    }
+
+    // Legal moves ending on one of the targets, castling ends on the king's square.
+    // Only those moves are generated, the full move map isn't needed.
+    pub fn moves_onto(&self, targets: Bitboard) -> Vec<LegalMove> {
+        if self.move_map.get().is_some() {
+            return self
+                .legal_moves()
+                .filter(|r#move| targets.contains(r#move.to))
+                .collect();
+        }
+
+        let legality = self.legality();
+        let mut moves = Vec::new();
+        for from in self.board.occupied_by(self.player) {
+            let piece = self.at_pos(from).get_type();
+            self.piece_moves(from, &legality, targets, &mut |r#move| {
+                moves.push(LegalMove::new(from, r#move, piece, self.player))
+            });
+        }
+
+        moves
+    }
+
+    pub fn is_legal(&self, from: Pos, r#move: Move) -> bool {
+        if self.move_map.get().is_some() {
+            return self
+                .moves_for_pos(from)
+                .is_some_and(|moves| moves.contains(&r#move));
+        }
+
+        let piece = self.at_pos(from);
+        if piece.is_empty() || piece.color() != self.player {
+            return false;
+        }
+
+        let mut found = false;
+        self.piece_moves(from, &self.legality(), Bitboard::FULL, &mut |legal| {
+            found |= legal == r#move
+        });
+        found
+    }
+
+    // Stops at the first legal move, trying the king first as it can most often move
+    pub fn has_legal_moves(&self) -> bool {
+        if self.move_map.get().is_some() {
+            return self.legal_moves().next().is_some();
+        }
+
+        let legality = self.legality();
+        let others = self.board.occupied_by(self.player) & !Bitboard::from_pos(self.king_pos);
+        std::iter::once(self.king_pos).chain(others).any(|from| {
+            let mut found = false;
+            self.piece_moves(from, &legality, Bitboard::FULL, &mut |_| found = true);
+            found
+        })
+    }
+
+    pub fn is_capture(&self, r#move: &LegalMove) -> bool {
+        match r#move.r#move {
+            Move::EnPassant(_) => true,
+            Move::KingSideCastling | Move::QueenSideCastling => false,
+            _ => !self.at_pos(r#move.to).is_empty(),
+        }
+    }
+
+    pub fn gives_check(&self, r#move: &LegalMove) -> bool {
+        let enemy = self.player.flip();
+        let board = self
+            .board
+            .after_move(r#move.from, r#move.r#move, self.player);
+        board.pos_in_danger(board.find_king(enemy), enemy)
+    }
+
+    pub fn captures(&self) -> impl Iterator<Item = LegalMove> + '_ {
+        self.legal_moves()
+            .filter(move |r#move| self.is_capture(r#move))
+    }
+
+    pub fn quiet_moves(&self) -> impl Iterator<Item = LegalMove> + '_ {
+        self.legal_moves()
+            .filter(move |r#move| !self.is_capture(r#move))
+    }
+
+    pub fn checks(&self) -> impl Iterator<Item = LegalMove> + '_ {
+        self.legal_moves()
+            .filter(move |r#move| self.gives_check(r#move))
+    }
+
+    pub fn moves_from(&self, from: Pos) -> impl Iterator<Item = LegalMove> + '_ {
+        self.legal_moves().filter(move |r#move| r#move.from == from)
+    }
+
+    pub fn moves_to(&self, to: Pos) -> impl Iterator<Item = LegalMove> + '_ {
+        self.legal_moves().filter(move |r#move| r#move.to == to)
+    }
+
+    pub fn moves_by(&self, piece: PieceType) -> impl Iterator<Item = LegalMove> + '_ {
+        self.legal_moves()
+            .filter(move |r#move| r#move.piece == piece)
+    }
+}
@@ -1,38 +1,85 @@
+use crate::bitboard::Bitboard;
+use crate::board::{zobrist, MoveUndo};
 use crate::{Board, Color, Move, MoveMap, PieceType, Pos, TaggedPiece};
 
+use std::cell::OnceCell;
+
+mod draw;
+mod fen;
+mod legal_moves;
 mod moves;
-mod pgn;
+mod perft;
+mod san;
+mod uci;
+
+pub use draw::DrawReason;
+pub use fen::START_FEN;
+pub use legal_moves::LegalMove;
+pub use san::ParseSanError;
+pub use uci::ParseUciError;
 
 #[cfg(test)]
 mod tests;
 
-#[derive(PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum GameResult {
     InvalidMove,
     Ok,
     Checkmate,
     Stalemate,
+    Draw(DrawReason),
+}
+
+// State make_move overwrites, everything else is recalculated when unmaking
+#[derive(Clone)]
+struct HistoryEntry {
+    from: Pos,
+    r#move: Move,
+    undo: MoveUndo,
+    en_passant: Option<Pos>,
+    halfmove_clock: u32,
+    zobrist: u64,
+    // Restored as is, regenerating it would make every unmake as slow as a make
+    move_map: OnceCell<MoveMap>,
 }
 
+#[derive(Clone)]
 pub struct Game {
     board: Board,
-    move_map: MoveMap,
-    history: Vec<(Board, Pos, Move)>,
+    // Built the first time something asks for the moves, searches often never do
+    move_map: OnceCell<MoveMap>,
+    history: Vec<HistoryEntry>,
+    positions: Vec<u64>,
     player: Color,
     pub king_pos: Pos,
+    en_passant: Option<Pos>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    start_fen: String,
+    zobrist: u64,
+    // Castling is written king takes rook in UCI notation
+    chess960: bool,
 }
 
 impl Default for Game {
     fn default() -> Self {
         let mut game = Game {
             board: Default::default(),
-            move_map: MoveMap::new(),
+            move_map: OnceCell::new(),
             history: Vec::new(),
+            positions: Vec::new(),
             player: Color::White,
             king_pos: Pos::new_xy(4, 0),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            start_fen: START_FEN.to_string(),
+            zobrist: 0,
+            chess960: false,
         };
 
-        game.calculate_all_moves();
+        game.zobrist = game.calculate_zobrist();
+        game.positions.push(game.zobrist);
 
         game
     }
@@ -42,21 +89,100 @@ impl Game {
     pub fn from_board(board: Board, player: Color) -> Self {
         let mut game = Self {
             board,
-            move_map: MoveMap::new(),
+            move_map: OnceCell::new(),
             history: Vec::new(),
+            positions: Vec::new(),
             player,
             king_pos: board.find_king(player),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            start_fen: String::new(),
+            zobrist: 0,
+            chess960: board.has_chess960_castling(),
         };
 
-        game.calculate_all_moves();
+        game.start_fen = game.to_fen();
+        game.zobrist = game.calculate_zobrist();
+        game.positions.push(game.zobrist);
 
         game
     }
+
+    // Without a start FEN, for looking at millions of positions like the
+    // tablebase generator does
+    pub(crate) fn from_position(board: Board, player: Color) -> Self {
+        let mut game = Self {
+            board,
+            move_map: OnceCell::new(),
+            history: Vec::new(),
+            positions: Vec::new(),
+            player,
+            king_pos: board.find_king(player),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            start_fen: String::new(),
+            zobrist: 0,
+            chess960: false,
+        };
+
+        game.zobrist = game.calculate_zobrist();
+        game.positions.push(game.zobrist);
+
+        game
+    }
+
+    // Chess960 start position by number, see Board::chess960
+    pub fn chess960(index: u16) -> Option<Self> {
+        let mut game = Self::from_board(Board::chess960(index)?, Color::White);
+        game.chess960 = true;
+        Some(game)
+    }
+
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    // Set automatically for positions only Chess960 allows, this is for the
+    // standard start position and others that could be either
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    // Returns true when the side now to move has no moves
     pub fn switch_side(&mut self) -> bool {
-        self.move_map.clear();
+        self.zobrist ^= self.en_passant_zobrist() ^ zobrist::side_key();
+        self.en_passant = None;
+        self.pass_turn();
+        self.positions.push(self.zobrist);
+        self.move_map().is_empty()
+    }
+
+    // The same position with the other side to move and no history, for looking at the
+    // moves it would have. Like after any move there is no en passant capture.
+    pub(crate) fn after_null_move(&self) -> Self {
+        let player = self.player.flip();
+        Self {
+            board: self.board,
+            move_map: OnceCell::new(),
+            history: Vec::new(),
+            positions: Vec::new(),
+            player,
+            king_pos: self.board.find_king(player),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock + 1,
+            fullmove_number: self.fullmove_number + (self.player == Color::Black) as u32,
+            start_fen: String::new(),
+            zobrist: self.zobrist ^ self.en_passant_zobrist() ^ zobrist::side_key(),
+            chess960: self.chess960,
+        }
+    }
+
+    fn pass_turn(&mut self) {
+        self.move_map = OnceCell::new();
         self.player = self.player.flip();
         self.king_pos = self.board.find_king(self.player);
-        self.calculate_all_moves()
     }
 
     pub fn current_color(&self) -> Color {
@@ -67,8 +193,46 @@ impl Game {
         self.board.pos_in_danger(self.king_pos, self.player)
     }
 
+    pub fn en_passant(&self) -> Option<Pos> {
+        self.en_passant
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn start_fen(&self) -> &str {
+        &self.start_fen
+    }
+
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    pub fn played_moves(&self) -> impl Iterator<Item = (Pos, Move)> + '_ {
+        self.history.iter().map(|entry| (entry.from, entry.r#move))
+    }
+
+    pub fn status(&self) -> GameResult {
+        if self.move_map().is_empty() {
+            if self.is_king_in_danger() {
+                GameResult::Checkmate
+            } else {
+                GameResult::Stalemate
+            }
+        } else if let Some(reason) = self.automatic_draw() {
+            GameResult::Draw(reason)
+        } else {
+            GameResult::Ok
+        }
+    }
+
     pub fn moves_for_pos(&self, pos: Pos) -> Option<&[Move]> {
-        self.move_map.at(pos)
+        self.move_map().at(pos)
     }
 
     pub fn print_ascii(&self) {
@@ -87,7 +251,7 @@ impl Game {
         assert!(from.index() < 64);
         assert!(!self.at_pos(from).is_empty());
 
-        let moves = self.move_map.at(from);
+        let moves = self.move_map().at(from);
 
         if moves.is_none() {
             return GameResult::InvalidMove;
@@ -99,28 +263,88 @@ impl Game {
             return GameResult::InvalidMove;
         }
 
-        self.history.push((self.board, from, r#move));
-        self.board = self.board.after_move(from, r#move, self.player);
+        self.make_move(from, r#move);
+        self.status()
+    }
 
-        if self.switch_side() {
-            if self.board.pos_in_danger(self.king_pos, self.player) {
-                GameResult::Checkmate
-            } else {
-                GameResult::Stalemate
+    // Plays a move from the move map without checking that it is legal
+    pub fn make_move(&mut self, from: Pos, r#move: Move) {
+        let mut zobrist = self.zobrist
+            ^ self.board.castling_zobrist()
+            ^ self.en_passant_zobrist()
+            ^ self.board.move_zobrist(from, r#move, self.player)
+            ^ zobrist::side_key();
+
+        let undo = self.board.make_move(from, r#move, self.player);
+        self.history.push(HistoryEntry {
+            from,
+            r#move,
+            undo,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            zobrist: self.zobrist,
+            move_map: std::mem::take(&mut self.move_map),
+        });
+
+        let pawn = undo.moved.get_type() == PieceType::Pawn;
+        self.en_passant = match r#move {
+            Move::Move(to) if pawn && from.distance_y(&to) == 2 => {
+                Some(Pos::new_xy(from.x(), (from.y() + to.y()) / 2))
             }
+            _ => None,
+        };
+
+        if pawn || !undo.captured.is_empty() {
+            self.halfmove_clock = 0;
         } else {
-            GameResult::Ok
+            self.halfmove_clock += 1;
         }
+
+        if self.player == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        zobrist ^= self.board.castling_zobrist();
+
+        self.pass_turn();
+        self.zobrist = zobrist ^ self.en_passant_zobrist();
+        debug_assert_eq!(self.zobrist, self.calculate_zobrist());
+        self.positions.push(self.zobrist);
+    }
+
+    // Takes back the last move, returning it
+    pub fn unmake_move(&mut self) -> Option<(Pos, Move)> {
+        let entry = self.history.pop()?;
+        let color = self.player.flip();
+
+        self.board
+            .unmake_move(entry.from, entry.r#move, color, entry.undo);
+        self.en_passant = entry.en_passant;
+        self.halfmove_clock = entry.halfmove_clock;
+        self.zobrist = entry.zobrist;
+
+        if color == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        self.positions.pop();
+        self.player = color;
+        self.king_pos = self.board.find_king(color);
+        self.move_map = entry.move_map;
+
+        Some((entry.from, entry.r#move))
     }
 
     pub fn undo(&mut self, count: usize) -> bool {
-        if self.history.len() <= count {
-            self.board = self.history[self.history.len() - count].0;
-            self.history.truncate(self.history.len() - count);
-            true
-        } else {
-            false
+        if self.history.len() < count {
+            return false;
         }
+
+        for _ in 0..count {
+            self.unmake_move();
+        }
+
+        true
     }
 
     pub fn at_xy(&self, x: u8, y: u8) -> TaggedPiece {
@@ -139,52 +363,55 @@ impl Game {
         &self.board
     }
 
-    fn calculate_all_moves(&mut self) -> bool {
-        let mut moves: usize = 0;
-        for i in 0..64 {
-            moves += self.calculate_moves_for(Pos::new_index(i));
-        }
+    fn move_map(&self) -> &MoveMap {
+        self.move_map.get_or_init(|| {
+            let legality = self.legality();
+            let mut move_map = MoveMap::new();
 
-        moves == 0
-    }
+            for pos in self.board.occupied_by(self.player) {
+                move_map.set_current_pos(pos);
+                self.piece_moves(pos, &legality, Bitboard::FULL, &mut |r#move| {
+                    move_map.insert(r#move)
+                });
+            }
 
-    fn calculate_moves_for(&mut self, pos: Pos) -> usize {
-        let piece = self.at_pos(pos);
+            move_map
+        })
+    }
 
-        if piece.is_empty() || piece.color() != self.player {
-            return 0;
-        }
+    fn calculate_zobrist(&self) -> u64 {
+        let side = if self.player == Color::Black {
+            zobrist::side_key()
+        } else {
+            0
+        };
 
-        self.move_map.set_current_pos(pos);
+        self.board.zobrist() ^ side ^ self.en_passant_zobrist()
+    }
 
-        match piece.get_type() {
-            PieceType::Pawn => {
-                self.add_pawn_moves(pos);
+    // En passant only counts if the capture can actually be played
+    fn en_passant_zobrist(&self) -> u64 {
+        match self.en_passant {
+            Some(to)
+                if self
+                    .moves_onto(Bitboard::from_pos(to))
+                    .iter()
+                    .any(|r#move| r#move.r#move == Move::EnPassant(to)) =>
+            {
+                zobrist::en_passant_key(to.x())
             }
-            PieceType::Rook => {
-                self.add_straight_moves(pos);
-            }
-            PieceType::Knight => {
-                self.add_knight_moves(pos);
-            }
-            PieceType::Bishop => {
-                self.add_diagonal_moves(pos);
-            }
-            PieceType::Queen => {
-                self.add_diagonal_moves(pos);
-                self.add_straight_moves(pos);
-            }
-            PieceType::King => {
-                self.add_king_moves(pos);
-                self.add_castling_moves();
-            }
-        };
-
-        self.move_map.current_pos_moves_len()
+            _ => 0,
+        }
     }
 
     fn king_in_danger_after_move(&self, from: Pos, r#move: Move) -> bool {
-        let board_after_move = self.board.after_move(from, r#move, self.player);
-        board_after_move.pos_in_danger(self.king_pos, self.player)
+        let board = self.board.after_move(from, r#move, self.player);
+        let king_pos = if self.at_pos(from).get_type() == PieceType::King {
+            board.find_king(self.player)
+        } else {
+            self.king_pos
+        };
+
+        board.pos_in_danger(king_pos, self.player)
     }
 }
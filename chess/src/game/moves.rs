@@ -1,280 +1,226 @@
 mod inner {
-    use crate::{game::Game, Color, Move, PieceType, Pos, TaggedPiece};
-    impl Game {
-        pub fn add_pawn_moves(&mut self, from: Pos) {
-            let y_dir: i8 = if self.player == Color::White { 1 } else { -1 };
+    use crate::bitboard::{
+        between, bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks, Bitboard,
+    };
+    use crate::board::castling_targets;
+    use crate::{game::Game, Color, Move, PieceType, Pos};
+
+    // Checks and pins against the side to move, computed once per position
+    pub(crate) struct Legality {
+        checkers: Bitboard,
+        // Squares a non king move has to end on, blocking or capturing a single checker
+        check_mask: Bitboard,
+        // Line between the king and the pinning piece for pinned pieces, all squares otherwise
+        pin_masks: [Bitboard; 64],
+    }
 
-            let add_promotion_moves = |game: &mut Game, to: Pos| {
-                let r#move = Move::PawnPromotion(PieceType::Queen, to);
+    impl Legality {
+        fn mask(&self, from: Pos) -> Bitboard {
+            self.check_mask & self.pin_masks[from.index()]
+        }
+    }
 
-                if !game.king_in_danger_after_move(from, r#move) {
-                    game.move_map.insert(r#move);
-                    game.move_map
-                        .insert(Move::PawnPromotion(PieceType::Knight, to));
-                    game.move_map
-                        .insert(Move::PawnPromotion(PieceType::Bishop, to));
-                    game.move_map
-                        .insert(Move::PawnPromotion(PieceType::Rook, to));
-                }
+    impl Game {
+        pub(crate) fn legality(&self) -> Legality {
+            let enemy = self.player.flip();
+            let king = self.king_pos;
+
+            let checkers = self.board.attackers(king, enemy);
+            let check_mask = match checkers.count() {
+                0 => Bitboard::FULL,
+                1 => checkers | between(king, checkers.first().unwrap()),
+                _ => Bitboard::EMPTY,
             };
 
-            if let Some(to) = from.move_y(y_dir) {
-                if self.at_pos(to).is_empty() {
-                    // Promotion
-                    if to.at_y_edge() {
-                        add_promotion_moves(self, to);
-                    }
-                    // Standard forward
-                    else {
-                        let r#move = Move::Move(to);
-                        if !self.king_in_danger_after_move(from, r#move) {
-                            self.move_map.insert(r#move);
-                        }
-                    }
-                }
-            }
-
-            // First move, double forward
-            if from.at_pawn_rank(self.player) {
-                if let Some(to) = from.move_y(y_dir * 2) {
-                    if self.at_pos(to).is_empty()
-                        && self.at_pos(from.move_y(y_dir).unwrap()).is_empty()
-                    {
-                        let r#move = Move::Move(to);
-                        if !self.king_in_danger_after_move(from, r#move) {
-                            self.move_map.insert(r#move);
-                        }
-                    }
-                }
-            }
+            // Enemy sliders that would attack the king if our own pieces were gone
+            let enemies = self.board.occupied_by(enemy);
+            let queens = self.board.pieces_of(PieceType::Queen, enemy);
+            let snipers = (rook_attacks(king, enemies)
+                & (self.board.pieces_of(PieceType::Rook, enemy) | queens))
+                | (bishop_attacks(king, enemies)
+                    & (self.board.pieces_of(PieceType::Bishop, enemy) | queens));
 
-            let mut add_pawn_take = |to| {
-                let space = self.at_pos(to);
+            let mut pin_masks = [Bitboard::FULL; 64];
+            for sniper in snipers {
+                let line = between(king, sniper);
+                let blockers = line & self.board.occupied();
 
-                // Standard diagonal pawn take
-                if !space.is_empty() && space.color() != self.player {
-                    if to.at_y_edge() {
-                        add_promotion_moves(self, to);
-                    } else {
-                        let r#move = Move::Move(to);
-                        if !self.king_in_danger_after_move(from, r#move) {
-                            self.move_map.insert(r#move);
-                        }
-                    }
-                }
-                // En passant
-                else if let Some((_, last_from, last_move)) = self.history.last() {
-                    if let Move::Move(last_to) = last_move {
-                        if last_from.distance_y(&last_to) == 2 {
-                            let space = self.at_pos(*last_to);
-                            if !space.is_empty()
-                                && space.color() != self.player
-                                && space.get_type() == PieceType::Pawn
-                            {
-                                if let Some(new_pos) = last_from.move_y(y_dir * -1) {
-                                    if new_pos == to {
-                                        let r#move = Move::EnPassant(to);
-                                        if !self.king_in_danger_after_move(from, r#move) {
-                                            self.move_map.insert(r#move);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+                if blockers.count() == 1 {
+                    let pinned = blockers.first().unwrap();
+                    pin_masks[pinned.index()] = line | Bitboard::from_pos(sniper);
                 }
-            };
-
-            let y_forward = from.move_y(y_dir).unwrap();
-            if let Some(pos) = y_forward.add_x(1) {
-                add_pawn_take(pos);
             }
 
-            if let Some(pos) = y_forward.sub_x(1) {
-                add_pawn_take(pos);
+            Legality {
+                checkers,
+                check_mask,
+                pin_masks,
             }
         }
 
-        pub fn add_straight_moves(&mut self, from: Pos) {
-            let mut loop_internal = |to| {
-                let space = self.board.at_pos(to);
-                if space.is_empty() || space.color() != self.player {
-                    let r#move = Move::Move(to);
-                    if !self.king_in_danger_after_move(from, r#move) {
-                        self.move_map.insert(r#move);
-                    }
+        // Moves of the piece on from that end on one of the targets, castling counts
+        // as ending on the king's square
+        pub(crate) fn piece_moves(
+            &self,
+            from: Pos,
+            legality: &Legality,
+            targets: Bitboard,
+            push: &mut impl FnMut(Move),
+        ) {
+            let occupied = self.board.occupied();
+            match self.at_pos(from).get_type() {
+                PieceType::Pawn => self.add_pawn_moves(from, legality, targets, push),
+                PieceType::Knight => {
+                    self.add_attack_moves(from, knight_attacks(from), legality, targets, push)
+                }
+                PieceType::Bishop => {
+                    let attacks = bishop_attacks(from, occupied);
+                    self.add_attack_moves(from, attacks, legality, targets, push)
+                }
+                PieceType::Rook => {
+                    let attacks = rook_attacks(from, occupied);
+                    self.add_attack_moves(from, attacks, legality, targets, push)
+                }
+                PieceType::Queen => {
+                    let attacks = bishop_attacks(from, occupied) | rook_attacks(from, occupied);
+                    self.add_attack_moves(from, attacks, legality, targets, push)
+                }
+                PieceType::King => {
+                    self.add_king_moves(from, targets, push);
+                    self.add_castling_moves(legality, targets, push);
                 }
+            }
+        }
 
-                space.is_empty()
-            };
+        fn add_pawn_moves(
+            &self,
+            from: Pos,
+            legality: &Legality,
+            targets: Bitboard,
+            push: &mut impl FnMut(Move),
+        ) {
+            let y_dir: i8 = if self.player == Color::White { 1 } else { -1 };
+            let occupied = self.board.occupied();
 
-            let (x, y) = from.xy();
+            let mut to_squares = Bitboard::EMPTY;
 
-            // Right
-            for x in (x + 1)..8 {
-                if !loop_internal(Pos::new_xy(x, y)) {
-                    break;
-                }
-            }
+            let forward = from.move_y(y_dir).unwrap();
+            if !occupied.contains(forward) {
+                to_squares.insert(forward);
 
-            // Left
-            for dist in 1..(x + 1) {
-                if !loop_internal(from.sub_x(dist).unwrap()) {
-                    break;
+                // First move, double forward
+                if from.at_pawn_rank(self.player) {
+                    let to = forward.move_y(y_dir).unwrap();
+                    if !occupied.contains(to) {
+                        to_squares.insert(to);
+                    }
                 }
             }
 
-            // Up
-            for y in (y + 1)..8 {
-                if !loop_internal(Pos::new_xy(x, y)) {
-                    break;
-                }
-            }
+            to_squares |=
+                pawn_attacks(from, self.player) & self.board.occupied_by(self.player.flip());
 
-            // Down
-            for dist in 1..(y + 1) {
-                if !loop_internal(from.sub_y(dist).unwrap()) {
-                    break;
+            for to in to_squares & targets & legality.mask(from) {
+                if to.at_y_edge() {
+                    push(Move::PawnPromotion(PieceType::Queen, to));
+                    push(Move::PawnPromotion(PieceType::Knight, to));
+                    push(Move::PawnPromotion(PieceType::Bishop, to));
+                    push(Move::PawnPromotion(PieceType::Rook, to));
+                } else {
+                    push(Move::Move(to));
                 }
             }
-        }
-
-        pub fn add_diagonal_moves(&mut self, from: Pos) {
-            use std::cmp::min;
-
-            let mut check = |x_dir: i8, y_dir: i8, dist: u8| {
-                let dist = dist as i8;
-                for off in 1..=dist {
-                    let to = from.move_xy(off * x_dir, off * y_dir).unwrap();
-                    let piece = self.at_pos(to);
 
-                    if piece.is_empty() || piece.color() != self.player {
-                        let r#move = Move::Move(to);
-                        if !self.king_in_danger_after_move(from, r#move) {
-                            self.move_map.insert(r#move);
-                        }
-                    }
-
-                    if !piece.is_empty() {
-                        break;
+            // Removing two pawns from a rank can uncover a check no pin shows, so en passant
+            // is the one move still tested by playing it
+            if let Some(to) = self.en_passant {
+                if targets.contains(to) && pawn_attacks(from, self.player).contains(to) {
+                    let r#move = Move::EnPassant(to);
+                    if !self.king_in_danger_after_move(from, r#move) {
+                        push(r#move);
                     }
                 }
-            };
-
-            // SW
-            check(-1, -1, min(from.x(), from.y()));
-
-            // SE
-            check(1, -1, min(7 - from.x(), from.y()));
-
-            // NE
-            check(1, 1, min(7 - from.x(), 7 - from.y()));
-
-            // NW
-            check(-1, 1, min(from.x(), 7 - from.y()));
+            }
         }
 
-        pub fn add_knight_moves(&mut self, from: Pos) {
-            let mut add_move = |x_dir: i8, y_dir: i8| {
-                let mut add = |to| {
-                    let piece = self.at_pos(to);
-                    if piece.is_empty() || piece.color() != self.player {
-                        let r#move = Move::Move(to);
-                        if !self.king_in_danger_after_move(from, r#move) {
-                            self.move_map.insert(r#move);
-                        }
-                    }
-                };
-
-                if let Some(to) = from.move_xy(x_dir, y_dir * 2) {
-                    add(to);
-                }
-
-                if let Some(to) = from.move_xy(x_dir * 2, y_dir) {
-                    add(to);
-                }
-            };
-
-            add_move(1, 1);
-            add_move(1, -1);
-            add_move(-1, -1);
-            add_move(-1, 1);
+        fn add_attack_moves(
+            &self,
+            from: Pos,
+            attacks: Bitboard,
+            legality: &Legality,
+            targets: Bitboard,
+            push: &mut impl FnMut(Move),
+        ) {
+            let own = self.board.occupied_by(self.player);
+            for to in attacks & !own & targets & legality.mask(from) {
+                push(Move::Move(to));
+            }
         }
 
-        pub fn add_king_moves(&mut self, from: Pos) {
-            let mut check = |x_dir: i8, y_dir: i8| {
-                if let Some(to) = from.move_xy(x_dir, y_dir) {
-                    let piece = self.at_pos(to);
+        fn add_king_moves(&self, from: Pos, targets: Bitboard, push: &mut impl FnMut(Move)) {
+            let enemy = self.player.flip();
 
-                    if piece.is_empty() || piece.color() != self.player {
-                        let r#move = Move::Move(to);
-                        let board_after_move = self.board.after_move(from, r#move, self.player);
+            // Without the king, so it can't hide behind itself from a slider
+            let occupied = self.board.occupied() ^ Bitboard::from_pos(from);
 
-                        if !board_after_move.pos_in_danger(to, self.player) {
-                            self.move_map.insert(r#move);
-                        }
-                    }
+            for to in king_attacks(from) & !self.board.occupied_by(self.player) & targets {
+                if self.board.attackers_with(to, enemy, occupied).is_empty() {
+                    push(Move::Move(to));
                 }
-            };
-
-            check(1, 1);
-            check(1, 0);
-            check(0, 1);
-            check(0, -1);
-            check(-1, 0);
-            check(-1, 1);
-            check(-1, -1);
-            check(1, -1);
+            }
         }
 
-        pub fn add_castling_moves(&mut self) {
-            let y = if self.player == Color::White { 0 } else { 7 };
-            let king_pos = Pos::new_xy(4, y);
-
-            if self.king_pos != king_pos || self.is_king_in_danger() {
+        fn add_castling_moves(
+            &self,
+            legality: &Legality,
+            targets: Bitboard,
+            push: &mut impl FnMut(Move),
+        ) {
+            if !legality.checkers.is_empty() {
                 return;
             }
 
-            let mut board_without_king = self.board;
-            board_without_king.set_pos(king_pos, TaggedPiece::empty());
-
-            let empty_and_not_in_check = |x| {
-                let to = Pos::new_xy(x, y);
-                if !self.at_pos(to).is_empty() {
-                    return false;
-                }
-
-                if board_without_king.pos_in_danger(to, self.player) {
-                    return false;
+            for &r#move in [Move::KingSideCastling, Move::QueenSideCastling].iter() {
+                let (king_to, _) = castling_targets(self.king_pos, r#move);
+                if !targets.contains(king_to) {
+                    continue;
                 }
 
-                true
-            };
-
-            let (r#move, king_x) = if self.at_xy(0, y).is_original()
-                && empty_and_not_in_check(1)
-                && empty_and_not_in_check(2)
-                && empty_and_not_in_check(3)
-            {
-                (Move::QueenSideCastling, 3)
-            } else if self.at_xy(7, y).is_original()
-                && empty_and_not_in_check(5)
-                && empty_and_not_in_check(6)
-            {
-                (Move::KingSideCastling, 6)
-            } else {
-                (Move::None, 0)
-            };
+                let rook = self
+                    .board
+                    .castling_rook(self.player, r#move == Move::KingSideCastling);
 
-            if r#move == Move::None {
-                return;
+                if let Some(rook) = rook {
+                    if self.can_castle_with(rook, r#move) {
+                        push(r#move);
+                    }
+                }
             }
+        }
 
-            let board_with_move = self.board.after_move(king_pos, r#move, self.player);
-            let new_king_pos = Pos::new_xy(king_x, y);
-            if !board_with_move.pos_in_danger(new_king_pos, self.player) {
-                self.move_map.insert(r#move);
+        // The king and rook go to the same squares as in standard chess, from
+        // wherever they start in Chess960
+        fn can_castle_with(&self, rook: Pos, r#move: Move) -> bool {
+            let king = self.king_pos;
+            let (king_to, rook_to) = castling_targets(king, r#move);
+            let pieces = Bitboard::from_pos(king) | Bitboard::from_pos(rook);
+
+            // Everything both pieces pass over has to be empty, apart from the two of them
+            let path = between(king, king_to)
+                | Bitboard::from_pos(king_to)
+                | between(rook, rook_to)
+                | Bitboard::from_pos(rook_to);
+            if !(path & self.board.occupied() & !pieces).is_empty() {
+                return false;
             }
+
+            // No square the king crosses may be attacked, including attacks the
+            // rook only blocks until it moves. The king can't hide an attack on
+            // its path, that would already be check.
+            let occupied = self.board.occupied() ^ Bitboard::from_pos(rook);
+            let enemy = self.player.flip();
+            (between(king, king_to) | Bitboard::from_pos(king_to))
+                .all(|pos| self.board.attackers_with(pos, enemy, occupied).is_empty())
         }
     }
 }
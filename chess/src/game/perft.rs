@@ -0,0 +1,50 @@
+use crate::{game::Game, Move, Pos};
+
+impl Game {
+    fn all_moves(&self) -> Vec<(Pos, Move)> {
+        self.legal_moves()
+            .map(|r#move| (r#move.from, r#move.r#move))
+            .collect()
+    }
+
+    fn perft_inner(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.all_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for (from, r#move) in moves {
+            self.make_move(from, r#move);
+            nodes += self.perft_inner(depth - 1);
+            self.unmake_move();
+        }
+
+        nodes
+    }
+
+    // Number of leaf nodes in the legal move tree of the given depth
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().perft_inner(depth)
+    }
+
+    // Perft split up by root move, for finding where move generation goes wrong
+    pub fn perft_divide(&self, depth: u32) -> Vec<(Pos, Move, u64)> {
+        assert!(depth > 0);
+
+        let mut game = self.clone();
+        self.all_moves()
+            .into_iter()
+            .map(|(from, r#move)| {
+                game.make_move(from, r#move);
+                let nodes = game.perft_inner(depth - 1);
+                game.unmake_move();
+                (from, r#move, nodes)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,306 @@
+use crate::{game::Game, Color, Move, PieceType, Pos};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ParseSanError {
+    Malformed,
+    NoSuchMove,
+    Ambiguous,
+    IllegalPromotion,
+}
+
+struct SanMove {
+    piece: PieceType,
+    from_x: Option<u8>,
+    from_y: Option<u8>,
+    capture: bool,
+    to: Pos,
+    promotion: Option<PieceType>,
+}
+
+fn strip_annotations(san: &str) -> &str {
+    let annotations = &['+', '#', '!', '?'][..];
+    let san = san.trim().trim_end_matches(annotations);
+    let san = san.strip_suffix("e.p.").unwrap_or(san);
+    san.trim_end().trim_end_matches(annotations)
+}
+
+fn parse_castling(san: &str) -> Option<Move> {
+    match san {
+        "O-O" | "0-0" | "o-o" => Some(Move::KingSideCastling),
+        "O-O-O" | "0-0-0" | "o-o-o" => Some(Move::QueenSideCastling),
+        _ => None,
+    }
+}
+
+fn file(c: u8) -> Option<u8> {
+    if (b'a'..=b'h').contains(&c) {
+        Some(c - b'a')
+    } else {
+        None
+    }
+}
+
+fn rank(c: u8) -> Option<u8> {
+    if (b'1'..=b'8').contains(&c) {
+        Some(c - b'1')
+    } else {
+        None
+    }
+}
+
+fn lex_san(san: &str) -> Result<SanMove, ParseSanError> {
+    if !san.is_ascii() {
+        return Err(ParseSanError::Malformed);
+    }
+
+    let mut bytes = san.as_bytes();
+
+    let piece = match bytes.first() {
+        Some(c) if c.is_ascii_uppercase() => {
+            bytes = &bytes[1..];
+            PieceType::from_char(*c as char).ok_or(ParseSanError::Malformed)?
+        }
+        Some(_) => PieceType::Pawn,
+        None => return Err(ParseSanError::Malformed),
+    };
+
+    // Promotion, with or without the '='
+    let mut promotion = None;
+    if let [rest @ .., b'=', c] = bytes {
+        let r#type = PieceType::from_char(c.to_ascii_uppercase() as char);
+        promotion = Some(r#type.ok_or(ParseSanError::IllegalPromotion)?);
+        bytes = rest;
+    } else if let [rest @ .., c] = bytes {
+        if c.is_ascii_uppercase() {
+            promotion = Some(PieceType::from_char(*c as char).ok_or(ParseSanError::Malformed)?);
+            bytes = rest;
+        }
+    }
+
+    let to = match bytes {
+        [rest @ .., x, y] => {
+            bytes = rest;
+            match (file(*x), rank(*y)) {
+                (Some(x), Some(y)) => Pos::new_xy(x, y),
+                _ => return Err(ParseSanError::Malformed),
+            }
+        }
+        _ => return Err(ParseSanError::Malformed),
+    };
+
+    let mut capture = false;
+    match bytes {
+        [rest @ .., b'x'] | [rest @ .., b':'] => {
+            capture = true;
+            bytes = rest;
+        }
+        [rest @ .., b'-'] => bytes = rest,
+        _ => {}
+    }
+
+    let (from_x, from_y) = match bytes {
+        [] => (None, None),
+        [c] => match (file(*c), rank(*c)) {
+            (Some(x), _) => (Some(x), None),
+            (_, Some(y)) => (None, Some(y)),
+            _ => return Err(ParseSanError::Malformed),
+        },
+        [x, y] => match (file(*x), rank(*y)) {
+            (Some(x), Some(y)) => (Some(x), Some(y)),
+            _ => return Err(ParseSanError::Malformed),
+        },
+        _ => return Err(ParseSanError::Malformed),
+    };
+
+    Ok(SanMove {
+        piece,
+        from_x,
+        from_y,
+        capture,
+        to,
+        promotion,
+    })
+}
+
+impl Game {
+    fn disambiguation(&self, from: Pos, to: Pos) -> String {
+        let piece = self.at_pos(from);
+        let r#move = Move::Move(to);
+
+        let mut ambiguous = false;
+        let mut same_x = false;
+        let mut same_y = false;
+        for i in 0..64 {
+            let pos = Pos::new_index(i);
+            if pos == from || self.at_pos(pos) != piece {
+                continue;
+            }
+
+            if let Some(moves) = self.moves_for_pos(pos) {
+                if moves.contains(&r#move) {
+                    ambiguous = true;
+                    same_x |= pos.x() == from.x();
+                    same_y |= pos.y() == from.y();
+                }
+            }
+        }
+
+        let file = ((b'a' + from.x()) as char).to_string();
+        let rank = (from.y() + 1).to_string();
+
+        if !ambiguous {
+            String::new()
+        } else if !same_x {
+            file
+        } else if !same_y {
+            rank
+        } else {
+            file + &rank
+        }
+    }
+
+    pub fn parse_san(&self, san: &str) -> Result<(Pos, Move), ParseSanError> {
+        let san = strip_annotations(san);
+
+        if let Some(r#move) = parse_castling(san) {
+            return match self.moves_for_pos(self.king_pos) {
+                Some(moves) if moves.contains(&r#move) => Ok((self.king_pos, r#move)),
+                _ => Err(ParseSanError::NoSuchMove),
+            };
+        }
+
+        let san = lex_san(san)?;
+        let to = san.to;
+
+        let last_rank = if self.player == Color::White { 7 } else { 0 };
+        match san.promotion {
+            Some(PieceType::Pawn) | Some(PieceType::King) => {
+                return Err(ParseSanError::IllegalPromotion)
+            }
+            Some(_) if san.piece != PieceType::Pawn || to.y() != last_rank => {
+                return Err(ParseSanError::IllegalPromotion)
+            }
+            None if san.piece == PieceType::Pawn && to.y() == last_rank => {
+                return Err(ParseSanError::IllegalPromotion)
+            }
+            _ => {}
+        }
+
+        let candidates = match san.promotion {
+            Some(r#type) => [Move::PawnPromotion(r#type, to), Move::None],
+            None if san.piece == PieceType::Pawn => [Move::Move(to), Move::EnPassant(to)],
+            None => [Move::Move(to), Move::None],
+        };
+
+        // A pawn without a file can only push straight forward
+        let from_x = match san.from_x {
+            None if san.piece == PieceType::Pawn => Some(to.x()),
+            from_x => from_x,
+        };
+
+        let mut found = None;
+        for i in 0..64 {
+            let from = Pos::new_index(i);
+            let piece = self.at_pos(from);
+            if piece.is_empty() || piece.color() != self.player || piece.get_type() != san.piece {
+                continue;
+            }
+
+            if from_x.is_some_and(|x| x != from.x()) || san.from_y.is_some_and(|y| y != from.y()) {
+                continue;
+            }
+
+            let moves = match self.moves_for_pos(from) {
+                Some(moves) => moves,
+                None => continue,
+            };
+
+            for r#move in candidates.iter() {
+                if *r#move == Move::None || !moves.contains(r#move) {
+                    continue;
+                }
+
+                if san.capture && *r#move != Move::EnPassant(to) && self.at_pos(to).is_empty() {
+                    continue;
+                }
+
+                if found.is_some() {
+                    return Err(ParseSanError::Ambiguous);
+                }
+                found = Some((from, *r#move));
+            }
+        }
+
+        found.ok_or(ParseSanError::NoSuchMove)
+    }
+
+    // None for moves that aren't legal in the position
+    // Without the check or mate suffix
+    fn san_body(&self, from: Pos, r#move: Move) -> Option<String> {
+        if !self.is_legal(from, r#move) {
+            return None;
+        }
+
+        let san = match r#move {
+            Move::KingSideCastling => "O-O".to_string(),
+            Move::QueenSideCastling => "O-O-O".to_string(),
+            Move::Move(to) | Move::EnPassant(to) | Move::PawnPromotion(_, to) => {
+                let piece = self.at_pos(from).get_type();
+                let capture = !self.at_pos(to).is_empty() || matches!(r#move, Move::EnPassant(_));
+
+                let mut san = String::with_capacity(8);
+                if piece == PieceType::Pawn {
+                    if capture {
+                        san.push((b'a' + from.x()) as char);
+                    }
+                } else {
+                    san.push(piece.to_char());
+                    san.push_str(&self.disambiguation(from, to));
+                }
+
+                if capture {
+                    san.push('x');
+                }
+                san.push_str(&to.to_string());
+
+                if let Move::PawnPromotion(r#type, _) = r#move {
+                    san.push('=');
+                    san.push(r#type.to_char());
+                }
+
+                san
+            }
+            Move::None => return None,
+        };
+
+        Some(san)
+    }
+
+    pub fn to_san(&self, from: Pos, r#move: Move) -> Option<String> {
+        let mut san = self.san_body(from, r#move)?;
+
+        // Only a check needs the position after the move, to tell if it is mate
+        let enemy = self.player.flip();
+        let board = self.board.after_move(from, r#move, self.player);
+        if board.pos_in_danger(board.find_king(enemy), enemy) {
+            let mut next = self.clone();
+            next.make_move(from, r#move);
+            san.push(if next.has_legal_moves() { '+' } else { '#' });
+        }
+
+        Some(san)
+    }
+
+    // Plays the move and returns its SAN, or None without playing it if it isn't legal.
+    // Going through a game this way needs no copy of it for the checks.
+    pub fn play_san(&mut self, from: Pos, r#move: Move) -> Option<String> {
+        let mut san = self.san_body(from, r#move)?;
+
+        self.make_move(from, r#move);
+        if self.is_king_in_danger() {
+            san.push(if self.has_legal_moves() { '+' } else { '#' });
+        }
+
+        Some(san)
+    }
+}
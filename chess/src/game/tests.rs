@@ -21,7 +21,7 @@ fn pawn_moves_new_board() {
                 Move::Move(from.move_y(y_dir * 2).unwrap()),
             ];
 
-            assert_eq!(game.moves_for_pos(from).unwrap(), &correct_moves);
+            compare_moves(game.moves_for_pos(from).unwrap(), &correct_moves);
         }
     };
 
@@ -42,6 +42,30 @@ fn rook_moves_new_board() {
     assert!(game.moves_for_pos(Pos::new_xy(7, 7)).unwrap().is_empty());
 }
 
+#[test]
+fn switch_side_keeps_zobrist() {
+    let same_as_fen = |game: &Game| {
+        assert_eq!(
+            game.zobrist(),
+            Game::from_fen(&game.to_fen()).unwrap().zobrist()
+        );
+    };
+
+    let mut game = Game::default();
+    game.switch_side();
+    same_as_fen(&game);
+    game.play(Pos::new_xy(4, 6), Move::move_xy(4, 4));
+    same_as_fen(&game);
+
+    // The en passant capture goes away with the turn
+    let mut game = Game::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    game.switch_side();
+    assert_eq!(game.en_passant(), None);
+    same_as_fen(&game);
+    game.play(Pos::new_xy(4, 3), Move::move_xy(4, 4));
+    same_as_fen(&game);
+}
+
 #[test]
 fn knight_moves_new_board() {
     let mut game = Game::default();
@@ -53,7 +77,7 @@ fn knight_moves_new_board() {
             Move::Move(move_pos.sub_x(1).unwrap()),
         ];
 
-        assert_eq!(game.moves_for_pos(pos).unwrap(), &correct_moves);
+        compare_moves(game.moves_for_pos(pos).unwrap(), &correct_moves);
     };
 
     check(&game, Pos::new_xy(1, 0), 2);
@@ -234,11 +258,10 @@ fn test_with_whole_game() {
             Kh8 Kb1 Qh4 Bc3 Bf6 f4 Nc4 Bxf6 Qxf6 Bd3 b5 Qe2 Bd7 Rhg1 Be8 Rde1 Bf7 Rg3 
             Rc8 Reg1 Nd6 Rxg7 Nf5 R7g5 Rc7 Bxf5 exf5 Rh5+ Bxh5 Qxh5+ Qh6 Qxh6+ Rh7 Qf8#";
 
-    let moves = moves.trim().split_whitespace();
+    let moves = moves.split_whitespace();
 
     for (i, str_move) in moves.enumerate() {
-        let (from, actual_move) = game.parse_pgn_move(str_move);
-        assert_ne!(actual_move, Move::None);
+        let (from, actual_move) = game.parse_san(str_move).unwrap();
 
         let result = game.play(from, actual_move);
         if result == GameResult::Checkmate {
@@ -249,3 +272,1036 @@ fn test_with_whole_game() {
         }
     }
 }
+
+#[test]
+fn fen_round_trip() {
+    let fens = [
+        START_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 b - - 10 10",
+    ];
+
+    for fen in fens.iter() {
+        let game = Game::from_fen(fen).unwrap();
+        assert_eq!(&game.to_fen(), fen);
+    }
+}
+
+#[test]
+fn fen_default_game() {
+    let game = Game::from_fen(START_FEN).unwrap();
+    assert_eq!(game.board(), Game::default().board());
+    assert_eq!(Game::default().to_fen(), START_FEN);
+}
+
+#[test]
+fn fen_after_moves() {
+    let mut game = Game::default();
+
+    game.play(Pos::new_xy(4, 1), Move::move_xy(4, 3));
+    assert_eq!(
+        game.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+
+    game.play(Pos::new_xy(2, 6), Move::move_xy(2, 4));
+    assert_eq!(
+        game.to_fen(),
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"
+    );
+
+    game.play(Pos::new_xy(6, 0), Move::move_xy(5, 2));
+    assert_eq!(
+        game.to_fen(),
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+
+    game.play(Pos::new_xy(7, 6), Move::move_xy(7, 5));
+    game.play(Pos::new_xy(7, 0), Move::move_xy(6, 0));
+    assert_eq!(
+        game.to_fen(),
+        "rnbqkbnr/pp1pppp1/7p/2p5/4P3/5N2/PPPP1PPP/RNBQKBR1 b Qkq - 1 3"
+    );
+}
+
+#[test]
+fn fen_en_passant() {
+    let game =
+        Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(4, 4)).unwrap();
+    assert!(moves.contains(&Move::EnPassant(Pos::new_xy(3, 5))));
+
+    let game =
+        Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3").unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(4, 4)).unwrap();
+    assert!(!moves.contains(&Move::EnPassant(Pos::new_xy(3, 5))));
+}
+
+#[test]
+fn fen_castling_rights() {
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1";
+    let game = Game::from_fen(fen).unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(4, 0)).unwrap();
+    assert!(!moves.contains(&Move::KingSideCastling));
+    assert!(!moves.contains(&Move::QueenSideCastling));
+
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w K - 0 1";
+    let game = Game::from_fen(fen).unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(4, 0)).unwrap();
+    assert!(moves.contains(&Move::KingSideCastling));
+
+    // Both sides at once, b1 being attacked does not prevent queen side castling
+    let fen = "1r2k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1";
+    let game = Game::from_fen(fen).unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(4, 0)).unwrap();
+    assert!(moves.contains(&Move::KingSideCastling));
+    assert!(moves.contains(&Move::QueenSideCastling));
+}
+
+#[test]
+fn fen_invalid() {
+    use crate::board::ParseFenError;
+
+    let invalid = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+            ParseFenError::MissingField,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            ParseFenError::InvalidColor,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+            ParseFenError::InvalidEnPassant,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - a 1",
+            ParseFenError::InvalidClock,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+            ParseFenError::InvalidClock,
+        ),
+        (
+            "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1",
+            ParseFenError::InvalidKings,
+        ),
+        (
+            "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            ParseFenError::InvalidPlacement,
+        ),
+    ];
+
+    for (fen, error) in invalid.iter() {
+        assert_eq!(Game::from_fen(fen).err().unwrap(), *error);
+    }
+}
+
+#[test]
+fn status() {
+    assert_eq!(Game::default().status(), GameResult::Ok);
+
+    let game = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(game.status(), GameResult::Stalemate);
+
+    let game = Game::from_fen("7k/6Q1/7K/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(game.status(), GameResult::Checkmate);
+}
+
+#[test]
+fn san_disambiguation() {
+    let game = Game::from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+    assert_eq!(
+        game.to_san(Pos::new_xy(1, 0), Move::move_xy(3, 1))
+            .as_deref(),
+        Some("Nbd2")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(5, 2), Move::move_xy(3, 1))
+            .as_deref(),
+        Some("Nfd2")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(5, 2), Move::move_xy(4, 4))
+            .as_deref(),
+        Some("Ne5")
+    );
+
+    let game = Game::from_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1").unwrap();
+    assert_eq!(
+        game.to_san(Pos::new_xy(0, 0), Move::move_xy(0, 2))
+            .as_deref(),
+        Some("R1a3")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(0, 4), Move::move_xy(0, 2))
+            .as_deref(),
+        Some("R5a3")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(0, 0), Move::move_xy(1, 0))
+            .as_deref(),
+        Some("Rb1")
+    );
+
+    let game = Game::from_fen("8/8/8/7k/8/Q7/8/Q1Q4K w - - 0 1").unwrap();
+    assert_eq!(
+        game.to_san(Pos::new_xy(0, 0), Move::move_xy(1, 1))
+            .as_deref(),
+        Some("Qa1b2")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(0, 2), Move::move_xy(1, 1))
+            .as_deref(),
+        Some("Q3b2")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(2, 0), Move::move_xy(1, 1))
+            .as_deref(),
+        Some("Qcb2")
+    );
+}
+
+#[test]
+fn san_pinned_piece_is_not_ambiguous() {
+    // The knight on e2 is pinned by the rook on e8
+    let game = Game::from_fen("k3r3/8/8/8/8/8/4N3/2N1K3 w - - 0 1").unwrap();
+    assert_eq!(
+        game.to_san(Pos::new_xy(2, 0), Move::move_xy(3, 2))
+            .as_deref(),
+        Some("Nd3")
+    );
+}
+
+#[test]
+fn san_pawn_moves() {
+    let game = Game::from_fen("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let from = Pos::new_xy(4, 6);
+    assert_eq!(
+        game.to_san(
+            from,
+            Move::PawnPromotion(PieceType::Queen, Pos::new_xy(3, 7))
+        )
+        .as_deref(),
+        Some("exd8=Q+")
+    );
+    assert_eq!(
+        game.to_san(
+            from,
+            Move::PawnPromotion(PieceType::Knight, Pos::new_xy(4, 7))
+        )
+        .as_deref(),
+        Some("e8=N")
+    );
+    assert_eq!(
+        game.to_san(
+            from,
+            Move::PawnPromotion(PieceType::Rook, Pos::new_xy(4, 7))
+        )
+        .as_deref(),
+        Some("e8=R+")
+    );
+
+    let game =
+        Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").unwrap();
+    assert_eq!(
+        game.to_san(Pos::new_xy(4, 4), Move::EnPassant(Pos::new_xy(3, 5)))
+            .as_deref(),
+        Some("exd6")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(4, 4), Move::move_xy(4, 5))
+            .as_deref(),
+        Some("e6")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(0, 1), Move::move_xy(0, 3))
+            .as_deref(),
+        Some("a4")
+    );
+}
+
+#[test]
+fn san_castling_and_checks() {
+    let game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let king = Pos::new_xy(4, 0);
+    assert_eq!(
+        game.to_san(king, Move::KingSideCastling).as_deref(),
+        Some("O-O")
+    );
+    assert_eq!(
+        game.to_san(king, Move::QueenSideCastling).as_deref(),
+        Some("O-O-O")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(7, 0), Move::move_xy(7, 7))
+            .as_deref(),
+        Some("Rxh8+")
+    );
+
+    let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    assert_eq!(
+        game.to_san(Pos::new_xy(0, 0), Move::move_xy(0, 7))
+            .as_deref(),
+        Some("Ra8#")
+    );
+    assert_eq!(
+        game.to_san(Pos::new_xy(0, 0), Move::move_xy(0, 6))
+            .as_deref(),
+        Some("Ra7")
+    );
+}
+
+#[test]
+fn san_of_illegal_moves() {
+    let game = Game::default();
+    assert_eq!(game.to_san(Pos::new_xy(4, 3), Move::move_xy(4, 4)), None);
+    assert_eq!(game.to_san(Pos::new_xy(4, 1), Move::None), None);
+    assert_eq!(game.to_san(Pos::new_xy(4, 1), Move::move_xy(4, 4)), None);
+    assert_eq!(game.to_san(Pos::new_xy(4, 6), Move::move_xy(4, 4)), None);
+}
+
+#[test]
+fn play_san() {
+    let mut game = Game::default();
+    for (uci, san) in [
+        ("f2f3", "f3"),
+        ("e7e5", "e5"),
+        ("g2g4", "g4"),
+        ("d8h4", "Qh4#"),
+    ] {
+        let (from, r#move) = game.parse_uci(uci).unwrap();
+        assert_eq!(game.to_san(from, r#move).as_deref(), Some(san));
+        assert_eq!(game.play_san(from, r#move).as_deref(), Some(san));
+    }
+    assert_eq!(game.status(), GameResult::Checkmate);
+
+    // Nothing is played for an illegal move
+    let mut game = Game::default();
+    assert_eq!(game.play_san(Pos::new_xy(4, 1), Move::move_xy(4, 4)), None);
+    assert_eq!(game.to_fen(), START_FEN);
+}
+
+#[test]
+fn parse_san_lenient() {
+    let game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    let king = Pos::new_xy(4, 0);
+
+    for san in ["O-O", "0-0", "o-o", "O-O+", "O-O\n", " O-O \r\n"].iter() {
+        assert_eq!(game.parse_san(san), Ok((king, Move::KingSideCastling)));
+    }
+
+    let game = Game::from_fen("r3k3/8/8/8/8/8/4P3/R3K3 w Q - 0 1").unwrap();
+
+    for san in ["O-O-O", "0-0-0", "O-O-O!?"].iter() {
+        assert_eq!(game.parse_san(san), Ok((king, Move::QueenSideCastling)));
+    }
+
+    let e2 = Pos::new_xy(4, 1);
+    for san in ["e4", "e4\n", "Pe4", "e2e4", "e2-e4", "e4!", "e4?!"].iter() {
+        assert_eq!(game.parse_san(san), Ok((e2, Move::move_xy(4, 3))));
+    }
+
+    let a1 = Pos::new_xy(0, 0);
+    for san in ["Rxa8+", "Ra8", "Ra1xa8", "Ra1-a8", "R1a8", "R:a8"].iter() {
+        assert_eq!(game.parse_san(san), Ok((a1, Move::move_xy(0, 7))));
+    }
+}
+
+#[test]
+fn parse_san_promotions() {
+    let game = Game::from_fen("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let e7 = Pos::new_xy(4, 6);
+    let d8 = Pos::new_xy(3, 7);
+    let e8 = Pos::new_xy(4, 7);
+
+    assert_eq!(
+        game.parse_san("exd8=Q+"),
+        Ok((e7, Move::PawnPromotion(PieceType::Queen, d8)))
+    );
+    assert_eq!(
+        game.parse_san("exd8N"),
+        Ok((e7, Move::PawnPromotion(PieceType::Knight, d8)))
+    );
+    assert_eq!(
+        game.parse_san("e8=b"),
+        Ok((e7, Move::PawnPromotion(PieceType::Bishop, e8)))
+    );
+    assert_eq!(
+        game.parse_san("e8=R"),
+        Ok((e7, Move::PawnPromotion(PieceType::Rook, e8)))
+    );
+
+    assert_eq!(game.parse_san("e8"), Err(ParseSanError::IllegalPromotion));
+    assert_eq!(game.parse_san("e8=K"), Err(ParseSanError::IllegalPromotion));
+    assert_eq!(game.parse_san("e8=P"), Err(ParseSanError::IllegalPromotion));
+    assert_eq!(
+        game.parse_san("Kd2=Q"),
+        Err(ParseSanError::IllegalPromotion)
+    );
+}
+
+#[test]
+fn parse_san_en_passant() {
+    let game =
+        Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").unwrap();
+    let e5 = Pos::new_xy(4, 4);
+    let d6 = Pos::new_xy(3, 5);
+
+    assert_eq!(game.parse_san("exd6"), Ok((e5, Move::EnPassant(d6))));
+    assert_eq!(game.parse_san("exd6 e.p."), Ok((e5, Move::EnPassant(d6))));
+    assert_eq!(game.parse_san("d6"), Err(ParseSanError::NoSuchMove));
+}
+
+#[test]
+fn parse_san_errors() {
+    let game = Game::from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+
+    assert_eq!(game.parse_san("Nd2"), Err(ParseSanError::Ambiguous));
+    assert_eq!(
+        game.parse_san("Nbd2"),
+        Ok((Pos::new_xy(1, 0), Move::move_xy(3, 1)))
+    );
+    assert_eq!(
+        game.parse_san("N3d2"),
+        Ok((Pos::new_xy(5, 2), Move::move_xy(3, 1)))
+    );
+
+    assert_eq!(
+        game.parse_san("Nd4"),
+        Ok((Pos::new_xy(5, 2), Move::move_xy(3, 3)))
+    );
+    assert_eq!(game.parse_san("Nxd4"), Err(ParseSanError::NoSuchMove));
+    assert_eq!(game.parse_san("Nd5"), Err(ParseSanError::NoSuchMove));
+    assert_eq!(game.parse_san("Qd5"), Err(ParseSanError::NoSuchMove));
+    assert_eq!(game.parse_san("O-O"), Err(ParseSanError::NoSuchMove));
+
+    for san in [
+        "", "+", "N", "Nd", "Nd9", "Ni4", "Xd4", "Nd4x", "Nbcd2", "Nd2 Nd4", "e4é",
+    ]
+    .iter()
+    {
+        assert_eq!(
+            game.parse_san(san),
+            Err(ParseSanError::Malformed),
+            "{}",
+            san
+        );
+    }
+}
+
+#[test]
+fn uci_round_trip() {
+    let mut game = Game::default();
+    let moves = [
+        "e2e4", "d7d5", "e4e5", "f7f5", "e5f6", "g8h6", "f6g7", "e8f7", "g7h8q", "d8d6", "g1f3",
+        "b8c6", "f1c4", "c8e6", "e1g1", "e8c8",
+    ];
+
+    for (i, uci) in moves.iter().enumerate() {
+        if *uci == "e8c8" {
+            // The king has already moved, so black may not castle
+            assert_eq!(game.parse_uci(uci), Err(ParseUciError::NoSuchMove));
+            break;
+        }
+
+        let (from, r#move) = game.parse_uci(uci).unwrap();
+        assert_eq!(&game.to_uci(from, r#move), uci, "move {}", i);
+        assert_ne!(game.play(from, r#move), GameResult::InvalidMove);
+    }
+
+    assert_eq!(
+        game.to_fen(),
+        "r4b1Q/ppp1pk1p/2nqb2n/3p4/2B5/5N2/PPPP1PPP/RNBQ1RK1 b - - 6 8"
+    );
+}
+
+#[test]
+fn parse_uci_special_moves() {
+    let game =
+        Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").unwrap();
+    assert_eq!(
+        game.parse_uci("e5d6"),
+        Ok((Pos::new_xy(4, 4), Move::EnPassant(Pos::new_xy(3, 5))))
+    );
+
+    let game = Game::from_fen("r3k3/1P6/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    assert_eq!(
+        game.parse_uci("e1g1"),
+        Ok((Pos::new_xy(4, 0), Move::KingSideCastling))
+    );
+    assert_eq!(
+        game.parse_uci("b7a8n"),
+        Ok((
+            Pos::new_xy(1, 6),
+            Move::PawnPromotion(PieceType::Knight, Pos::new_xy(0, 7))
+        ))
+    );
+    assert_eq!(
+        game.parse_uci("b7b8Q\n"),
+        Ok((
+            Pos::new_xy(1, 6),
+            Move::PawnPromotion(PieceType::Queen, Pos::new_xy(1, 7))
+        ))
+    );
+    assert_eq!(
+        game.to_uci(Pos::new_xy(4, 0), Move::QueenSideCastling),
+        "e1c1"
+    );
+    assert_eq!(game.to_uci(Pos::new_xy(4, 0), Move::None), "0000");
+
+    assert_eq!(game.parse_uci("b7b8"), Err(ParseUciError::NoSuchMove));
+    assert_eq!(game.parse_uci("h1h2q"), Err(ParseUciError::NoSuchMove));
+    assert_eq!(game.parse_uci("a8a7"), Err(ParseUciError::NoSuchMove));
+    assert_eq!(game.parse_uci("e1c1"), Err(ParseUciError::NoSuchMove));
+    assert_eq!(game.parse_uci("b7b8k"), Err(ParseUciError::Malformed));
+    assert_eq!(game.parse_uci("e1"), Err(ParseUciError::Malformed));
+    assert_eq!(game.parse_uci("e1e9"), Err(ParseUciError::Malformed));
+    assert_eq!(game.parse_uci("0000"), Err(ParseUciError::Malformed));
+}
+
+#[test]
+fn repetition_draws() {
+    let mut game = Game::default();
+    let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+
+    assert_eq!(game.repetitions(), 1);
+
+    for round in 1..=4 {
+        for (i, san) in shuffle.iter().enumerate() {
+            let (from, r#move) = game.parse_san(san).unwrap();
+            let result = game.play(from, r#move);
+
+            if round == 4 && i == 3 {
+                assert_eq!(result, GameResult::Draw(DrawReason::FivefoldRepetition));
+            } else {
+                assert_eq!(result, GameResult::Ok);
+            }
+        }
+
+        assert_eq!(game.repetitions(), round + 1);
+        if round >= 2 {
+            assert_eq!(game.can_claim_draw(), Some(DrawReason::ThreefoldRepetition));
+        } else {
+            assert_eq!(game.can_claim_draw(), None);
+        }
+    }
+
+    assert_eq!(
+        game.status(),
+        GameResult::Draw(DrawReason::FivefoldRepetition)
+    );
+}
+
+#[test]
+fn repetition_needs_same_castling_rights() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    for san in ["Rb1", "Kd7", "Ra1", "Ke8", "Rb1", "Kd7", "Ra1", "Ke8"].iter() {
+        let (from, r#move) = game.parse_san(san).unwrap();
+        game.play(from, r#move);
+    }
+
+    // The first position still had queen side castling rights
+    assert_eq!(game.repetitions(), 2);
+    assert_eq!(game.can_claim_draw(), None);
+}
+
+#[test]
+fn move_rule_draws() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 98 80").unwrap();
+    let (from, r#move) = game.parse_san("Ra2").unwrap();
+    assert_eq!(game.play(from, r#move), GameResult::Ok);
+    assert_eq!(game.can_claim_draw(), None);
+
+    let (from, r#move) = game.parse_san("Kd7").unwrap();
+    assert_eq!(game.play(from, r#move), GameResult::Ok);
+    assert_eq!(game.can_claim_draw(), Some(DrawReason::FiftyMoveRule));
+
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 149 80").unwrap();
+    let (from, r#move) = game.parse_san("Kd7").unwrap();
+    assert_eq!(
+        game.play(from, r#move),
+        GameResult::Draw(DrawReason::SeventyFiveMoveRule)
+    );
+
+    // Checkmate takes precedence over the seventy-five move rule
+    let mut game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 149 80").unwrap();
+    let (from, r#move) = game.parse_san("Ra8").unwrap();
+    assert_eq!(game.play(from, r#move), GameResult::Checkmate);
+}
+
+#[test]
+fn insufficient_material_draw() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1").unwrap();
+    let (from, r#move) = game.parse_san("Kxd2").unwrap();
+    assert_eq!(
+        game.play(from, r#move),
+        GameResult::Draw(DrawReason::InsufficientMaterial)
+    );
+}
+
+fn play_sans(game: &mut Game, sans: &[&str]) {
+    for san in sans.iter() {
+        let (from, r#move) = game.parse_san(san).unwrap();
+        assert_ne!(game.play(from, r#move), GameResult::InvalidMove, "{}", san);
+    }
+}
+
+#[test]
+fn zobrist_transpositions() {
+    let mut a = Game::default();
+    let mut b = Game::default();
+    play_sans(&mut a, &["Nf3", "Nf6", "e3", "e6"]);
+    play_sans(&mut b, &["e3", "e6", "Nf3", "Nf6"]);
+    assert_eq!(a.zobrist(), b.zobrist());
+
+    // Same placement with the other side to move
+    let white = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let black = Game::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert_eq!(white.board(), black.board());
+    assert_ne!(white.zobrist(), black.zobrist());
+}
+
+#[test]
+fn zobrist_en_passant_and_castling() {
+    // A double push without an adjacent enemy pawn does not change the key
+    let mut game = Game::default();
+    play_sans(&mut game, &["e4"]);
+    let fen = Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_eq!(game.zobrist(), fen.zobrist());
+
+    let with_ep =
+        Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").unwrap();
+    let without_ep =
+        Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3").unwrap();
+    assert_ne!(with_ep.zobrist(), without_ep.zobrist());
+
+    let castling = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let no_castling = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1").unwrap();
+    assert_ne!(castling.zobrist(), no_castling.zobrist());
+}
+
+#[test]
+fn zobrist_matches_fen_after_moves() {
+    let mut game = Game::default();
+    let moves = [
+        "e2e4", "d7d5", "e4e5", "f7f5", "e5f6", "g8h6", "f6g7", "e8f7", "g7h8q", "d8d6", "g1f3",
+        "b8c6", "f1c4", "c8e6", "e1g1",
+    ];
+
+    for uci in moves.iter() {
+        let (from, r#move) = game.parse_uci(uci).unwrap();
+        game.play(from, r#move);
+
+        let fresh = Game::from_fen(&game.to_fen()).unwrap();
+        assert_eq!(game.zobrist(), fresh.zobrist(), "after {}", uci);
+    }
+}
+
+fn check_perft(fen: &str, counts: &[u64]) {
+    let game = Game::from_fen(fen).unwrap();
+    for (depth, &count) in counts.iter().enumerate() {
+        assert_eq!(
+            game.perft(depth as u32 + 1),
+            count,
+            "depth {} of {}",
+            depth + 1,
+            fen
+        );
+    }
+}
+
+#[test]
+fn perft_initial() {
+    check_perft(START_FEN, &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn perft_kiwipete() {
+    check_perft(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97862],
+    );
+}
+
+#[test]
+fn perft_position_3() {
+    check_perft(
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2812, 43238],
+    );
+}
+
+#[test]
+fn perft_position_4() {
+    check_perft(
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9467],
+    );
+}
+
+#[test]
+fn perft_position_5() {
+    check_perft(
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1486, 62379],
+    );
+}
+
+#[test]
+fn perft_position_6() {
+    check_perft(
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2079, 89890],
+    );
+}
+
+#[test]
+fn perft_chess960() {
+    check_perft(
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        &[21, 528, 12189],
+    );
+    check_perft(
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        &[21, 807, 18002],
+    );
+    check_perft(
+        "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+        &[22, 593, 13440],
+    );
+}
+
+#[test]
+fn perft_divide_sums_to_perft() {
+    let game = Game::default();
+    let divide = game.perft_divide(2);
+
+    assert_eq!(divide.len(), 20);
+    assert!(divide.iter().all(|&(_, _, count)| count == 20));
+    assert_eq!(
+        divide.iter().map(|&(_, _, count)| count).sum::<u64>(),
+        game.perft(2)
+    );
+}
+
+fn assert_same_state(game: &Game, other: &Game) {
+    assert_eq!(game.to_fen(), other.to_fen());
+    assert_eq!(game.zobrist(), other.zobrist());
+    assert_eq!(game.repetitions(), other.repetitions());
+    assert_eq!(game.king_pos, other.king_pos);
+    for i in 0..64 {
+        let pos = Pos::new_index(i);
+        assert_eq!(game.moves_for_pos(pos), other.moves_for_pos(pos), "{}", pos);
+    }
+}
+
+#[test]
+fn make_unmake_restores_position() {
+    // Castling, promotion with capture and en passant are all available here
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+    ];
+
+    for fen in fens.iter() {
+        let mut game = Game::from_fen(fen).unwrap();
+        let original = game.clone();
+
+        for (from, r#move, _) in original.perft_divide(1) {
+            game.make_move(from, r#move);
+            assert_eq!(
+                Game::from_fen(&game.to_fen()).unwrap().zobrist(),
+                game.zobrist()
+            );
+            assert_eq!(game.unmake_move(), Some((from, r#move)));
+            assert_same_state(&game, &original);
+        }
+    }
+}
+
+#[test]
+fn staged_generation_matches_move_map() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        "4r1k1/8/8/8/1b6/8/3N4/R3K3 w Q - 0 1",
+        "8/8/8/8/8/8/6k1/4K2R w K - 0 1",
+    ];
+
+    for fen in fens.iter() {
+        // Each asks a fresh game, before anything built the move map
+        let fresh = || Game::from_fen(fen).unwrap();
+        let game = fresh();
+        let all: Vec<LegalMove> = game.legal_moves().collect();
+
+        let enemies = game.board().occupied_by(game.current_color().flip());
+        for &targets in [enemies, !enemies, Bitboard::rank(0), Bitboard::FULL].iter() {
+            let mut expected: Vec<LegalMove> = all
+                .iter()
+                .copied()
+                .filter(|r#move| targets.contains(r#move.to))
+                .collect();
+            let mut staged = fresh().moves_onto(targets);
+
+            let key = |r#move: &LegalMove| format!("{:?}", r#move);
+            expected.sort_by_key(key);
+            staged.sort_by_key(key);
+            assert_eq!(staged, expected, "{}", fen);
+        }
+
+        assert!(fresh().has_legal_moves());
+        for r#move in all.iter() {
+            assert!(fresh().is_legal(r#move.from, r#move.r#move));
+        }
+        assert!(!fresh().is_legal(Pos::new_xy(4, 0), Move::move_xy(4, 7)));
+    }
+
+    let mate = Game::from_fen("R5k1/5ppp/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert!(!mate.has_legal_moves());
+    let stalemate = Game::from_fen("7k/5Q2/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert!(!stalemate.has_legal_moves());
+}
+
+#[test]
+fn undo() {
+    let mut game = Game::default();
+    let start = game.clone();
+    play_sans(&mut game, &["e4", "d5", "exd5", "Nf6"]);
+    let middle = game.clone();
+    play_sans(&mut game, &["Bb5+", "c6", "dxc6"]);
+
+    assert!(!game.undo(8));
+    assert!(game.undo(3));
+    assert_same_state(&game, &middle);
+    assert_eq!(game.played_moves().count(), 4);
+
+    assert!(game.undo(4));
+    assert_same_state(&game, &start);
+    assert_eq!(game.unmake_move(), None);
+}
+
+#[test]
+fn check_evasions() {
+    // Only capturing or blocking the rook, or moving the king, answers the check
+    let game = Game::from_fen("4r1k1/8/8/8/8/8/3N1P2/R3K3 w Q - 0 1").unwrap();
+    compare_moves(
+        game.moves_for_pos(Pos::new_xy(4, 0)).unwrap(),
+        &[Move::move_xy(3, 0), Move::move_xy(5, 0)],
+    );
+    compare_moves(
+        game.moves_for_pos(Pos::new_xy(3, 1)).unwrap(),
+        &[Move::move_xy(4, 3)],
+    );
+    assert!(game.moves_for_pos(Pos::new_xy(5, 1)).unwrap().is_empty());
+    assert!(game.moves_for_pos(Pos::new_xy(0, 0)).unwrap().is_empty());
+
+    // Double check leaves only king moves
+    let game = Game::from_fen("4r1k1/8/8/8/1b6/8/3N4/R3K3 w Q - 0 1").unwrap();
+    assert!(game.moves_for_pos(Pos::new_xy(3, 1)).unwrap().is_empty());
+    assert!(!game.moves_for_pos(Pos::new_xy(4, 0)).unwrap().is_empty());
+}
+
+#[test]
+fn pinned_pieces() {
+    // The bishop is pinned along the diagonal and may only move along it
+    let game = Game::from_fen("6k1/8/8/8/8/2q5/3B4/4K3 w - - 0 1").unwrap();
+    compare_moves(
+        game.moves_for_pos(Pos::new_xy(3, 1)).unwrap(),
+        &[Move::move_xy(2, 2)],
+    );
+
+    // A pinned knight can never move
+    let game = Game::from_fen("4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+    assert!(game.moves_for_pos(Pos::new_xy(4, 1)).unwrap().is_empty());
+}
+
+#[test]
+fn en_passant_discovered_check() {
+    // Capturing would remove both pawns from the fifth rank and expose the king
+    let game = Game::from_fen("8/8/8/KPp4r/8/8/8/7k w - c6 0 2").unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(1, 4)).unwrap();
+    assert!(!moves.contains(&Move::EnPassant(Pos::new_xy(2, 5))));
+    assert!(moves.contains(&Move::move_xy(1, 5)));
+
+    // The pawn giving check can be taken en passant
+    let game = Game::from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1").unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(4, 3)).unwrap();
+    assert!(moves.contains(&Move::EnPassant(Pos::new_xy(3, 2))));
+}
+
+#[test]
+fn legal_moves() {
+    let game = Game::default();
+    assert_eq!(game.legal_moves().count(), 20);
+    assert_eq!(game.quiet_moves().count(), 20);
+    assert_eq!(game.captures().count(), 0);
+    assert_eq!(game.moves_by(PieceType::Knight).count(), 4);
+
+    let e4: Vec<LegalMove> = game.moves_to(Pos::new_xy(4, 3)).collect();
+    assert_eq!(e4.len(), 1);
+    assert_eq!(e4[0].from, Pos::new_xy(4, 1));
+    assert_eq!(e4[0].piece, PieceType::Pawn);
+    assert_eq!(e4[0].r#move, Move::move_xy(4, 3));
+
+    // Kiwipete has castling, captures, checks and a double push next to an enemy pawn
+    let game =
+        Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    assert_eq!(game.legal_moves().count(), 48);
+    assert_eq!(game.captures().count(), 8);
+    assert_eq!(game.quiet_moves().count(), 40);
+    assert_eq!(game.checks().count(), 0);
+
+    let castles: Vec<Pos> = game
+        .moves_from(Pos::new_xy(4, 0))
+        .map(|r#move| r#move.to)
+        .collect();
+    assert!(castles.contains(&Pos::new_xy(6, 0)));
+    assert!(castles.contains(&Pos::new_xy(2, 0)));
+
+    let game = Game::from_fen("4k3/1P6/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let checks: Vec<String> = game
+        .checks()
+        .map(|r#move| game.to_uci(r#move.from, r#move.r#move))
+        .collect();
+    assert_eq!(checks, ["a1a8", "b7b8q", "b7b8r"]);
+}
+
+#[test]
+fn legal_moves_as_full_moves() {
+    use crate::FullMove;
+    use std::collections::HashSet;
+
+    let game =
+        Game::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1").unwrap();
+    let mut seen = HashSet::new();
+    for legal in game.legal_moves() {
+        let full = FullMove::from(legal);
+        assert_eq!(full.origin(), legal.from);
+        assert_eq!(full.target(), legal.to);
+        assert_eq!(full.to_pair(), (legal.from, legal.r#move));
+        assert!(seen.insert(full.bits()));
+    }
+
+    assert_eq!(seen.len(), game.legal_moves().count());
+}
+
+#[test]
+fn chess960_games() {
+    let game = Game::chess960(518).unwrap();
+    assert!(game.is_chess960());
+    assert_eq!(game.to_fen(), START_FEN);
+    assert_eq!(game.perft(2), 400);
+
+    let game = Game::chess960(0).unwrap();
+    assert_eq!(
+        game.start_fen(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+    );
+    assert_eq!(
+        game.to_shredder_fen(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1"
+    );
+    assert!(Game::chess960(1000).is_none());
+
+    // Only positions standard chess can't have switch to Chess960 by themselves
+    assert!(!Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")
+        .unwrap()
+        .is_chess960());
+    assert!(Game::from_fen("r2k3r/8/8/8/8/8/8/R2K3R w KQkq - 0 1")
+        .unwrap()
+        .is_chess960());
+}
+
+#[test]
+fn chess960_castling() {
+    // The king side rook stands on the king's target square, the king on the queen side rook's
+    let mut game = Game::from_fen("1k6/8/8/8/8/8/8/R2K2R1 w AG - 0 1").unwrap();
+    let original = game.clone();
+    let king = Pos::new_xy(3, 0);
+
+    assert_eq!(game.parse_uci("d1g1"), Ok((king, Move::KingSideCastling)));
+    assert_eq!(game.parse_uci("d1a1"), Ok((king, Move::QueenSideCastling)));
+    assert_eq!(game.parse_san("O-O"), Ok((king, Move::KingSideCastling)));
+    assert_eq!(game.to_uci(king, Move::KingSideCastling), "d1g1");
+    assert_eq!(game.to_uci(king, Move::QueenSideCastling), "d1a1");
+
+    game.play(king, Move::KingSideCastling);
+    assert_eq!(game.to_fen(), "1k6/8/8/8/8/8/8/R4RK1 b - - 1 1");
+    game.unmake_move();
+    assert_same_state(&game, &original);
+
+    game.play(king, Move::QueenSideCastling);
+    assert_eq!(game.to_fen(), "1k6/8/8/8/8/8/8/2KR2R1 b - - 1 1");
+    game.unmake_move();
+    assert_same_state(&game, &original);
+
+    // A king already on its target square only moves the rook
+    let mut game = Game::from_fen("6k1/8/8/8/8/8/8/6KR w H - 0 1").unwrap();
+    let (from, r#move) = game.parse_uci("g1h1").unwrap();
+    assert_eq!(
+        crate::FullMove::from_pair(from, r#move).to_pair(),
+        (from, Move::KingSideCastling)
+    );
+    game.play(from, r#move);
+    assert_eq!(game.to_fen(), "6k1/8/8/8/8/8/8/5RK1 b - - 1 1");
+    assert_eq!(
+        game.zobrist(),
+        Game::from_fen(&game.to_fen()).unwrap().zobrist()
+    );
+}
+
+#[test]
+fn chess960_castling_legality() {
+    // The rook on b1 hides the attack on c1 until it moves away
+    let game = Game::from_fen("k7/8/8/8/8/8/8/rRK5 w B - 0 1").unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(2, 0)).unwrap();
+    assert!(!moves.contains(&Move::QueenSideCastling));
+
+    // Squares the rook passes over have to be empty as well
+    let game = Game::from_fen("k7/8/8/8/8/8/8/1K1N3R w H - 0 1").unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(1, 0)).unwrap();
+    assert!(!moves.contains(&Move::KingSideCastling));
+
+    // Every square the king crosses must be safe
+    let game = Game::from_fen("k3r3/8/8/8/8/8/8/1K5R w H - 0 1").unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(1, 0)).unwrap();
+    assert!(!moves.contains(&Move::KingSideCastling));
+    let game = Game::from_fen("k7/8/8/8/8/8/8/1K5R w H - 0 1").unwrap();
+    let moves = game.moves_for_pos(Pos::new_xy(1, 0)).unwrap();
+    assert!(moves.contains(&Move::KingSideCastling));
+}
+
+#[test]
+fn chess960_uci() {
+    let mut game = Game::default();
+    assert_eq!(
+        game.to_uci(Pos::new_xy(4, 0), Move::KingSideCastling),
+        "e1g1"
+    );
+
+    game.set_chess960(true);
+    assert_eq!(
+        game.to_uci(Pos::new_xy(4, 0), Move::KingSideCastling),
+        "e1h1"
+    );
+    assert_eq!(
+        game.to_uci(Pos::new_xy(4, 0), Move::QueenSideCastling),
+        "e1a1"
+    );
+
+    // Either form is understood
+    let game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let castling = Ok((Pos::new_xy(4, 0), Move::KingSideCastling));
+    assert_eq!(game.parse_uci("e1g1"), castling);
+    assert_eq!(game.parse_uci("e1h1"), castling);
+
+    let game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1").unwrap();
+    assert_eq!(game.parse_uci("e1h1"), Err(ParseUciError::NoSuchMove));
+}
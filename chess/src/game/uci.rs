@@ -0,0 +1,97 @@
+use crate::board::castling_targets;
+use crate::{game::Game, Color, Move, PieceType, Pos};
+
+use std::str::FromStr;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ParseUciError {
+    Malformed,
+    NoSuchMove,
+}
+
+impl Game {
+    pub fn parse_uci(&self, uci: &str) -> Result<(Pos, Move), ParseUciError> {
+        let uci = uci.trim();
+        if !uci.is_ascii() || (uci.len() != 4 && uci.len() != 5) {
+            return Err(ParseUciError::Malformed);
+        }
+
+        let from = Pos::from_str(&uci[0..2]).map_err(|_| ParseUciError::Malformed)?;
+        let to = Pos::from_str(&uci[2..4]).map_err(|_| ParseUciError::Malformed)?;
+
+        let promotion = match uci[4..].chars().next() {
+            Some(c) => match PieceType::from_char(c.to_ascii_uppercase()) {
+                Some(PieceType::Pawn) | Some(PieceType::King) | None => {
+                    return Err(ParseUciError::Malformed)
+                }
+                r#type => r#type,
+            },
+            None => None,
+        };
+
+        let piece = self.at_pos(from);
+        if piece.is_empty() || piece.color() != self.player {
+            return Err(ParseUciError::NoSuchMove);
+        }
+
+        let r#move = match (piece.get_type(), promotion) {
+            (PieceType::Pawn, Some(r#type)) => Move::PawnPromotion(r#type, to),
+            (_, Some(_)) => return Err(ParseUciError::NoSuchMove),
+            (PieceType::King, None) => self.parse_castling(from, to).unwrap_or(Move::Move(to)),
+            (PieceType::Pawn, None) if self.en_passant == Some(to) && from.x() != to.x() => {
+                Move::EnPassant(to)
+            }
+            _ => Move::Move(to),
+        };
+
+        match self.moves_for_pos(from) {
+            Some(moves) if moves.contains(&r#move) => Ok((from, r#move)),
+            _ => Err(ParseUciError::NoSuchMove),
+        }
+    }
+
+    // The king's two square move, or in Chess960 the king taking its own rook
+    fn parse_castling(&self, from: Pos, to: Pos) -> Option<Move> {
+        let king_side = to.x() > from.x();
+        let r#move = if king_side {
+            Move::KingSideCastling
+        } else {
+            Move::QueenSideCastling
+        };
+
+        let takes_rook = self.board.castling_rook(self.player, king_side) == Some(to);
+        let two_squares = from.distance_x(&to) == 2 && castling_targets(from, r#move).0 == to;
+        if from.y() == to.y() && (takes_rook || two_squares) {
+            Some(r#move)
+        } else {
+            None
+        }
+    }
+
+    // Castling is the king's two square move, or king takes rook in Chess960
+    // games where the king may not move at all
+    pub fn to_uci(&self, from: Pos, r#move: Move) -> String {
+        match r#move {
+            Move::Move(to) | Move::EnPassant(to) => format!("{}{}", from, to),
+            Move::PawnPromotion(r#type, to) => {
+                format!("{}{}{}", from, to, r#type.to_char().to_ascii_lowercase())
+            }
+            Move::KingSideCastling | Move::QueenSideCastling => {
+                let color = if from.y() == 0 {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                let rook = self
+                    .board
+                    .castling_rook(color, r#move == Move::KingSideCastling);
+
+                match rook {
+                    Some(rook) if self.chess960 => format!("{}{}", from, rook),
+                    _ => format!("{}{}", from, castling_targets(from, r#move).0),
+                }
+            }
+            Move::None => "0000".to_string(),
+        }
+    }
+}
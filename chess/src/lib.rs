@@ -1,15 +1,21 @@
+pub mod bitboard;
 pub mod board;
+pub mod book;
 pub mod color;
+pub mod engine;
 pub mod game;
 pub mod r#move;
+pub mod pgn;
 pub mod piece;
 pub mod pos;
+pub mod protocol;
+pub mod tablebase;
 
 pub use board::Board;
 pub use color::Color;
 pub use piece::{PieceType, TaggedPiece};
 pub use pos::Pos;
-pub use r#move::Move;
+pub use r#move::{FullMove, Move, MoveKind};
 
 mod move_map;
 use move_map::MoveMap;
@@ -12,27 +12,62 @@ fn main() {
     loop {
         buffer.clear();
         game.print_ascii();
-        println!("Move in pgn format: ");
+        println!("Move in pgn format (\"pgn\" prints the game, \"draw\" claims a draw): ");
         stdin.read_line(&mut buffer).unwrap();
 
-        let r#move = game.parse_pgn_move(&buffer);
-        let (from, r#move) = r#move;
+        if buffer.trim() == "pgn" {
+            print!("{}", game.to_pgn());
+            continue;
+        }
+
+        if buffer.trim() == "draw" {
+            if let Some(reason) = game.can_claim_draw() {
+                println!("Draw claimed: {:?}", reason);
+                print!("{}", game.to_pgn());
+                break;
+            }
+
+            println!("No draw can be claimed");
+            continue;
+        }
+
+        let r#move = game.parse_san(&buffer);
 
         // Clear the screen
         print!("\x1B[2J\x1B[1;1H");
 
+        let (from, r#move) = match r#move {
+            Ok(r#move) => r#move,
+            Err(error) => {
+                println!("Invalid move {}: {:?}", buffer.trim(), error);
+                continue;
+            }
+        };
+
+        let san = game.to_san(from, r#move);
         let result = game.play(from, r#move);
+        if let Some(san) = san {
+            println!("{:?} played {}", game.current_color().flip(), san);
+        }
+
         match result {
             GameResult::InvalidMove => {
-                println!("Invalid move: {}", buffer);
+                println!("Invalid move: {}", buffer.trim());
                 continue;
             }
             GameResult::Checkmate => {
                 println!("{:?} lost :(", game.current_color());
+                print!("{}", game.to_pgn());
                 break;
             }
             GameResult::Stalemate => {
                 println!("Stalemate!");
+                print!("{}", game.to_pgn());
+                break;
+            }
+            GameResult::Draw(reason) => {
+                println!("Draw: {:?}", reason);
+                print!("{}", game.to_pgn());
                 break;
             }
             _ => {}
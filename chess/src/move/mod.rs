@@ -0,0 +1,160 @@
+use crate::{PieceType, Pos};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[repr(u8)]
+pub enum Move {
+    None,
+    Move(Pos),
+    EnPassant(Pos),
+    PawnPromotion(PieceType, Pos),
+    KingSideCastling,
+    QueenSideCastling,
+}
+
+impl Move {
+    pub fn move_xy(x: u8, y: u8) -> Self {
+        Move::Move(Pos::new_xy(x, y))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum MoveKind {
+    Normal = 0,
+    Promotion = 1,
+    EnPassant = 2,
+    Castling = 3,
+}
+
+// A move with its origin packed into 16 bits: origin in bits 0-5, target in bits 6-11,
+// promotion piece in bits 12-13 and kind in bits 14-15. Castling moves go to the
+// king's destination square and the all zero value stands for Move::None.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FullMove(u16);
+
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+impl FullMove {
+    pub const NULL: Self = Self(0);
+
+    pub fn new(from: Pos, to: Pos, kind: MoveKind) -> Self {
+        Self(from.index() as u16 | ((to.index() as u16) << 6) | ((kind as u16) << 14))
+    }
+
+    pub fn promotion(from: Pos, to: Pos, r#type: PieceType) -> Self {
+        let piece = PROMOTIONS
+            .iter()
+            .position(|&t| t == r#type)
+            .expect("Can't promote to a pawn or king");
+
+        Self(Self::new(from, to, MoveKind::Promotion).0 | ((piece as u16) << 12))
+    }
+
+    pub fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    pub fn is_null(self) -> bool {
+        self == Self::NULL
+    }
+
+    pub fn origin(self) -> Pos {
+        Pos::new_index((self.0 & 0x3F) as u8)
+    }
+
+    pub fn target(self) -> Pos {
+        Pos::new_index(((self.0 >> 6) & 0x3F) as u8)
+    }
+
+    pub fn kind(self) -> MoveKind {
+        match self.0 >> 14 {
+            0 => MoveKind::Normal,
+            1 => MoveKind::Promotion,
+            2 => MoveKind::EnPassant,
+            _ => MoveKind::Castling,
+        }
+    }
+
+    pub fn promotion_type(self) -> Option<PieceType> {
+        if self.kind() == MoveKind::Promotion {
+            Some(PROMOTIONS[((self.0 >> 12) & 3) as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn from_pair(from: Pos, r#move: Move) -> Self {
+        let castling = |x| Self::new(from, Pos::new_xy(x, from.y()), MoveKind::Castling);
+
+        match r#move {
+            Move::None => Self::NULL,
+            Move::Move(to) => Self::new(from, to, MoveKind::Normal),
+            Move::EnPassant(to) => Self::new(from, to, MoveKind::EnPassant),
+            Move::PawnPromotion(r#type, to) => Self::promotion(from, to, r#type),
+            Move::KingSideCastling => castling(6),
+            Move::QueenSideCastling => castling(2),
+        }
+    }
+
+    pub fn to_pair(self) -> (Pos, Move) {
+        if self.is_null() {
+            return (self.origin(), Move::None);
+        }
+
+        let to = self.target();
+        let r#move = match self.kind() {
+            MoveKind::Normal => Move::Move(to),
+            MoveKind::Promotion => Move::PawnPromotion(self.promotion_type().unwrap(), to),
+            MoveKind::EnPassant => Move::EnPassant(to),
+            // The king can start anywhere in Chess960, but always ends on g or c
+            MoveKind::Castling if to.x() == 6 => Move::KingSideCastling,
+            MoveKind::Castling => Move::QueenSideCastling,
+        };
+
+        (self.origin(), r#move)
+    }
+}
+
+impl From<(Pos, Move)> for FullMove {
+    fn from((from, r#move): (Pos, Move)) -> Self {
+        Self::from_pair(from, r#move)
+    }
+}
+
+impl From<FullMove> for (Pos, Move) {
+    fn from(r#move: FullMove) -> Self {
+        r#move.to_pair()
+    }
+}
+
+impl std::fmt::Debug for FullMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_null() {
+            return write!(f, "FullMove(null)");
+        }
+
+        write!(
+            f,
+            "FullMove({}{} {:?}",
+            self.origin(),
+            self.target(),
+            self.kind()
+        )?;
+        if let Some(r#type) = self.promotion_type() {
+            write!(f, " {:?}", r#type)?;
+        }
+        write!(f, ")")
+    }
+}
@@ -0,0 +1,50 @@
+use super::*;
+
+#[test]
+fn full_move_round_trip() {
+    let e2 = Pos::new_xy(4, 1);
+    let e1 = Pos::new_xy(4, 0);
+    let e8 = Pos::new_xy(4, 7);
+    let pairs = [
+        (e2, Move::move_xy(4, 3)),
+        (Pos::new_xy(3, 4), Move::EnPassant(Pos::new_xy(4, 5))),
+        (
+            Pos::new_xy(1, 6),
+            Move::PawnPromotion(PieceType::Knight, Pos::new_xy(0, 7)),
+        ),
+        (
+            Pos::new_xy(7, 1),
+            Move::PawnPromotion(PieceType::Queen, Pos::new_xy(7, 0)),
+        ),
+        (e1, Move::KingSideCastling),
+        (e1, Move::QueenSideCastling),
+        (e8, Move::KingSideCastling),
+        (e8, Move::QueenSideCastling),
+        // Chess960 kings already on their castling target
+        (Pos::new_xy(6, 0), Move::KingSideCastling),
+        (Pos::new_xy(2, 7), Move::QueenSideCastling),
+    ];
+
+    for &pair in pairs.iter() {
+        let full = FullMove::from(pair);
+        assert_eq!(full.to_pair(), pair);
+        assert_eq!(FullMove::from_bits(full.bits()), full);
+    }
+
+    assert_eq!(FullMove::from((e1, Move::None)), FullMove::NULL);
+    assert_eq!(FullMove::NULL.to_pair().1, Move::None);
+}
+
+#[test]
+fn full_move_fields() {
+    let full = FullMove::from_pair(Pos::new_xy(4, 0), Move::KingSideCastling);
+    assert_eq!(full.origin(), Pos::new_xy(4, 0));
+    assert_eq!(full.target(), Pos::new_xy(6, 0));
+    assert_eq!(full.kind(), MoveKind::Castling);
+    assert_eq!(full.promotion_type(), None);
+
+    let full = FullMove::promotion(Pos::new_xy(0, 6), Pos::new_xy(0, 7), PieceType::Rook);
+    assert_eq!(full.kind(), MoveKind::Promotion);
+    assert_eq!(full.promotion_type(), Some(PieceType::Rook));
+    assert_eq!(full.bits(), 48 | (56 << 6) | (2 << 12) | (1 << 14));
+}
@@ -59,6 +59,7 @@ impl PieceMoves {
     }
 }
 
+#[derive(Clone)]
 pub struct MoveMap {
     moves: [Move; MAX_MOVES],
     pieces: [PieceMoves; MAX_PIECES],
@@ -115,18 +116,8 @@ impl MoveMap {
         }
     }
 
-    pub fn current_pos_moves_len(&self) -> usize {
-        self.pieces[self.current_piece_index].len()
-    }
-
     pub fn at(&self, pos: Pos) -> Option<&[Move]> {
-        let index = self.find_used_index(pos.index());
-
-        if index.is_none() {
-            return None;
-        }
-
-        let index = index.unwrap();
+        let index = self.find_used_index(pos.index())?;
         let moves = self.pieces[index];
         let len = moves.len() + moves.stack_index();
         Some(&self.moves[moves.stack_index()..len])
@@ -145,8 +136,7 @@ impl MoveMap {
         self.pieces[self.current_piece_index].increment_len();
     }
 
-    pub fn clear(&mut self) {
-        self.current_stack_index = 0;
-        self.pieces = [PieceMoves::empty(); MAX_PIECES];
+    pub fn is_empty(&self) -> bool {
+        self.current_stack_index == 0
     }
 }
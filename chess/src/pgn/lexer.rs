@@ -0,0 +1,196 @@
+use super::{Location, ParsePgnError, ParsePgnErrorKind};
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Token {
+    TagOpen,
+    TagClose,
+    String(String),
+    Symbol(String),
+    Period,
+    Asterisk,
+    Nag(u8),
+    Comment(String),
+    VariationOpen,
+    VariationClose,
+}
+
+pub struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+fn is_symbol_continuation(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_+#=:-/".contains(c)
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            chars: input.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub fn location(&self) -> Location {
+        Location {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, kind: ParsePgnErrorKind, location: Location) -> ParsePgnError {
+        ParsePgnError { kind, location }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '%' && self.column == 1 {
+                // Escaped line, ignored by the standard
+                self.skip_line();
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn read_string(&mut self, start: Location) -> Result<Token, ParsePgnError> {
+        let mut string = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(Token::String(string)),
+                Some('\\') => match self.bump() {
+                    Some(c) if c == '"' || c == '\\' => string.push(c),
+                    _ => return Err(self.error(ParsePgnErrorKind::InvalidEscape, self.location())),
+                },
+                Some('\n') | None => {
+                    return Err(self.error(ParsePgnErrorKind::UnterminatedString, start))
+                }
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn read_comment(&mut self, start: Location) -> Result<Token, ParsePgnError> {
+        let mut comment = String::new();
+        loop {
+            match self.bump() {
+                Some('}') => return Ok(Token::Comment(comment.trim().to_string())),
+                Some(c) => comment.push(c),
+                None => return Err(self.error(ParsePgnErrorKind::UnterminatedComment, start)),
+            }
+        }
+    }
+
+    fn read_line_comment(&mut self) -> Token {
+        let mut comment = String::new();
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+            comment.push(c);
+        }
+        Token::Comment(comment.trim().to_string())
+    }
+
+    fn read_nag(&mut self, start: Location) -> Result<Token, ParsePgnError> {
+        let mut digits = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            digits.push(c);
+            self.bump();
+        }
+
+        digits
+            .parse::<u8>()
+            .map(Token::Nag)
+            .map_err(|_| self.error(ParsePgnErrorKind::InvalidNag, start))
+    }
+
+    fn read_suffix(&mut self, first: char, start: Location) -> Result<Token, ParsePgnError> {
+        let second = match self.chars.peek() {
+            Some(&c) if c == '!' || c == '?' => {
+                self.bump();
+                Some(c)
+            }
+            _ => None,
+        };
+
+        let nag = match (first, second) {
+            ('!', None) => 1,
+            ('?', None) => 2,
+            ('!', Some('!')) => 3,
+            ('?', Some('?')) => 4,
+            ('!', Some('?')) => 5,
+            ('?', Some('!')) => 6,
+            _ => return Err(self.error(ParsePgnErrorKind::InvalidNag, start)),
+        };
+
+        Ok(Token::Nag(nag))
+    }
+
+    fn read_symbol(&mut self, first: char) -> Token {
+        let mut symbol = String::new();
+        symbol.push(first);
+        while let Some(&c) = self.chars.peek() {
+            if !is_symbol_continuation(c) {
+                break;
+            }
+            symbol.push(c);
+            self.bump();
+        }
+        Token::Symbol(symbol)
+    }
+
+    pub fn next_token(&mut self) -> Option<Result<(Token, Location), ParsePgnError>> {
+        self.skip_whitespace();
+
+        let start = self.location();
+        let c = self.bump()?;
+
+        let token = match c {
+            '[' => Ok(Token::TagOpen),
+            ']' => Ok(Token::TagClose),
+            '(' => Ok(Token::VariationOpen),
+            ')' => Ok(Token::VariationClose),
+            '.' => Ok(Token::Period),
+            '*' => Ok(Token::Asterisk),
+            '"' => self.read_string(start),
+            '{' => self.read_comment(start),
+            ';' => Ok(self.read_line_comment()),
+            '$' => self.read_nag(start),
+            '!' | '?' => self.read_suffix(c, start),
+            c if c.is_ascii_alphanumeric() => Ok(self.read_symbol(c)),
+            c => Err(self.error(ParsePgnErrorKind::UnexpectedChar(c), start)),
+        };
+
+        Some(token.map(|token| (token, start)))
+    }
+}
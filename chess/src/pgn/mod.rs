@@ -0,0 +1,162 @@
+use crate::game::{Game, GameResult, ParseSanError};
+
+mod lexer;
+pub mod reader;
+pub mod writer;
+
+pub use reader::PgnReader;
+pub use writer::PgnWriter;
+
+#[cfg(test)]
+mod tests;
+
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ParsePgnErrorKind {
+    UnexpectedChar(char),
+    UnexpectedToken,
+    UnterminatedString,
+    UnterminatedComment,
+    UnterminatedVariation,
+    InvalidEscape,
+    InvalidNag,
+    InvalidTag,
+    InvalidFen,
+    IllegalMove(String, ParseSanError),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParsePgnError {
+    pub kind: ParsePgnErrorKind,
+    pub location: Location,
+}
+
+impl std::fmt::Display for ParsePgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {:?}",
+            self.location.line, self.location.column, self.kind
+        )
+    }
+}
+
+impl std::error::Error for ParsePgnError {}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PgnResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl PgnResult {
+    pub fn from_symbol(s: &str) -> Option<Self> {
+        match s {
+            "1-0" => Some(PgnResult::WhiteWins),
+            "0-1" => Some(PgnResult::BlackWins),
+            "1/2-1/2" => Some(PgnResult::Draw),
+            "*" => Some(PgnResult::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PgnResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnResult::WhiteWins => write!(f, "1-0"),
+            PgnResult::BlackWins => write!(f, "0-1"),
+            PgnResult::Draw => write!(f, "1/2-1/2"),
+            PgnResult::Unknown => write!(f, "*"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PgnMove {
+    pub san: String,
+    pub nags: Vec<u8>,
+    pub comments_before: Vec<String>,
+    pub comments: Vec<String>,
+    pub variations: Vec<Vec<PgnMove>>,
+    pub location: Location,
+}
+
+impl PgnMove {
+    pub fn new(san: String, location: Location) -> Self {
+        PgnMove {
+            san,
+            nags: Vec::new(),
+            comments_before: Vec::new(),
+            comments: Vec::new(),
+            variations: Vec::new(),
+            location,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    pub result: PgnResult,
+    pub location: Location,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn starting_game(&self) -> Result<Game, ParsePgnError> {
+        let mut game = match self.tag("FEN") {
+            Some(fen) => Game::from_fen(fen).map_err(|_| ParsePgnError {
+                kind: ParsePgnErrorKind::InvalidFen,
+                location: self.location,
+            })?,
+            None => Game::default(),
+        };
+
+        if let Some(variant) = self.tag("Variant") {
+            let variant = variant.to_ascii_lowercase();
+            if variant == "chess960" || variant == "fischerandom" {
+                game.set_chess960(true);
+            }
+        }
+
+        Ok(game)
+    }
+
+    // Replays the main line from the starting position
+    pub fn to_game(&self) -> Result<Game, ParsePgnError> {
+        let mut game = self.starting_game()?;
+        for pgn_move in self.moves.iter() {
+            play_pgn_move(&mut game, pgn_move)?;
+        }
+        Ok(game)
+    }
+}
+
+pub fn play_pgn_move(game: &mut Game, pgn_move: &PgnMove) -> Result<GameResult, ParsePgnError> {
+    let (from, r#move) = game
+        .parse_san(&pgn_move.san)
+        .map_err(|error| ParsePgnError {
+            kind: ParsePgnErrorKind::IllegalMove(pgn_move.san.clone(), error),
+            location: pgn_move.location,
+        })?;
+
+    Ok(game.play(from, r#move))
+}
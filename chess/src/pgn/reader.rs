@@ -0,0 +1,175 @@
+use super::lexer::{Lexer, Token};
+use super::{Location, ParsePgnError, ParsePgnErrorKind, PgnGame, PgnMove, PgnResult};
+
+pub struct PgnReader<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<(Token, Location)>,
+    failed: bool,
+}
+
+impl<'a> PgnReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        PgnReader {
+            lexer: Lexer::new(input),
+            peeked: None,
+            failed: false,
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&(Token, Location)>, ParsePgnError> {
+        if self.peeked.is_none() {
+            self.peeked = match self.lexer.next_token() {
+                Some(token) => Some(token?),
+                None => None,
+            };
+        }
+
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next_token(&mut self) -> Result<Option<(Token, Location)>, ParsePgnError> {
+        self.peek()?;
+        Ok(self.peeked.take())
+    }
+
+    fn unexpected(location: Location) -> ParsePgnError {
+        ParsePgnError {
+            kind: ParsePgnErrorKind::UnexpectedToken,
+            location,
+        }
+    }
+
+    fn read_tag(&mut self, start: Location) -> Result<(String, String), ParsePgnError> {
+        let invalid = |location| ParsePgnError {
+            kind: ParsePgnErrorKind::InvalidTag,
+            location,
+        };
+
+        let name = match self.next_token()? {
+            Some((Token::Symbol(name), _)) => name,
+            Some((_, location)) => return Err(invalid(location)),
+            None => return Err(invalid(start)),
+        };
+
+        let value = match self.next_token()? {
+            Some((Token::String(value), _)) => value,
+            Some((_, location)) => return Err(invalid(location)),
+            None => return Err(invalid(start)),
+        };
+
+        match self.next_token()? {
+            Some((Token::TagClose, _)) => Ok((name, value)),
+            Some((_, location)) => Err(invalid(location)),
+            None => Err(invalid(start)),
+        }
+    }
+
+    fn read_moves(
+        &mut self,
+        depth: usize,
+        start: Location,
+    ) -> Result<(Vec<PgnMove>, Option<PgnResult>), ParsePgnError> {
+        let mut moves: Vec<PgnMove> = Vec::new();
+        let mut pending_comments = Vec::new();
+
+        loop {
+            let (token, location) = match self.peek()? {
+                Some((Token::TagOpen, _)) if depth == 0 => return Ok((moves, None)),
+                Some(token) => token.clone(),
+                None if depth == 0 => return Ok((moves, None)),
+                None => {
+                    return Err(ParsePgnError {
+                        kind: ParsePgnErrorKind::UnterminatedVariation,
+                        location: start,
+                    })
+                }
+            };
+            self.next_token()?;
+
+            match token {
+                Token::VariationClose if depth > 0 => return Ok((moves, None)),
+                Token::VariationOpen => {
+                    let (variation, _) = self.read_moves(depth + 1, location)?;
+                    match moves.last_mut() {
+                        Some(last) => last.variations.push(variation),
+                        None => return Err(Self::unexpected(location)),
+                    }
+                }
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(last) => last.comments.push(comment),
+                    None => pending_comments.push(comment),
+                },
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(last) => last.nags.push(nag),
+                    None => return Err(Self::unexpected(location)),
+                },
+                Token::Period => {}
+                Token::Asterisk if depth == 0 => return Ok((moves, Some(PgnResult::Unknown))),
+                Token::Symbol(symbol) => {
+                    if let Some(result) = PgnResult::from_symbol(&symbol) {
+                        if depth > 0 {
+                            return Err(Self::unexpected(location));
+                        }
+                        return Ok((moves, Some(result)));
+                    }
+
+                    // Move number indication
+                    if symbol.chars().all(|c| c.is_ascii_digit()) {
+                        continue;
+                    }
+
+                    let mut pgn_move = PgnMove::new(symbol, location);
+                    pgn_move.comments_before.append(&mut pending_comments);
+                    moves.push(pgn_move);
+                }
+                _ => return Err(Self::unexpected(location)),
+            }
+        }
+    }
+
+    fn read_game(&mut self) -> Result<Option<PgnGame>, ParsePgnError> {
+        let location = match self.peek()? {
+            Some((_, location)) => *location,
+            None => return Ok(None),
+        };
+
+        let mut tags = Vec::new();
+        while let Some((Token::TagOpen, start)) = self.peek()? {
+            let start = *start;
+            self.next_token()?;
+            tags.push(self.read_tag(start)?);
+        }
+
+        let (moves, result) = self.read_moves(0, location)?;
+
+        let mut game = PgnGame {
+            tags,
+            moves,
+            result: PgnResult::Unknown,
+            location,
+        };
+
+        game.result = result
+            .or_else(|| game.tag("Result").and_then(PgnResult::from_symbol))
+            .unwrap_or(PgnResult::Unknown);
+
+        Ok(Some(game))
+    }
+}
+
+impl<'a> Iterator for PgnReader<'a> {
+    type Item = Result<PgnGame, ParsePgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let game = self.read_game();
+        if game.is_err() {
+            self.failed = true;
+        }
+
+        game.transpose()
+    }
+}
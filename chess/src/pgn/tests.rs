@@ -0,0 +1,323 @@
+use super::*;
+
+use crate::game::ParseSanError;
+use crate::Color;
+
+const IMMORTAL_GAME: &str = r#"[Event "London"]
+[Site "London ENG"]
+[Date "1851.06.21"]
+[Round "?"]
+[White "Adolf Anderssen"]
+[Black "Lionel Adalbert Bagration Felix Kieseritzky"]
+[Result "1-0"]
+
+1.e4 e5 2.f4 exf4 3.Bc4 Qh4+ 4.Kf1 b5 5.Bxb5 Nf6 6.Nf3 Qh6 7.d3 Nh5 8.Nh4 Qg5
+9.Nf5 c6 10.g4 Nf6 11.Rg1 cxb5 12.h4 Qg6 13.h5 Qg5 14.Qf3 Ng8 15.Bxf4 Qf6
+16.Nc3 Bc5 17.Nd5 Qxb2 18.Bd6 Bxg1 19.e5 Qxa1+ 20.Ke2 Na6 21.Nxg7+ Kd8
+22.Qf6+ Nxf6 23.Be7# 1-0
+"#;
+
+fn read_one(input: &str) -> PgnGame {
+    let mut games = PgnReader::new(input);
+    let game = games.next().unwrap().unwrap();
+    assert!(games.next().is_none());
+    game
+}
+
+fn read_error(input: &str) -> ParsePgnError {
+    PgnReader::new(input)
+        .find_map(|game| game.err())
+        .expect("expected a parse error")
+}
+
+#[test]
+fn seven_tag_roster() {
+    let game = read_one(IMMORTAL_GAME);
+
+    for tag in SEVEN_TAG_ROSTER.iter() {
+        assert!(game.tag(tag).is_some());
+    }
+
+    assert_eq!(game.tag("Event"), Some("London"));
+    assert_eq!(
+        game.tag("Black"),
+        Some("Lionel Adalbert Bagration Felix Kieseritzky")
+    );
+    assert_eq!(game.tag("ECO"), None);
+    assert_eq!(game.result, PgnResult::WhiteWins);
+    assert_eq!(game.moves.len(), 45);
+    assert_eq!(game.moves[44].san, "Be7#");
+}
+
+#[test]
+fn replay() {
+    let game = read_one(IMMORTAL_GAME).to_game().unwrap();
+    assert_eq!(game.current_color(), Color::Black);
+    assert_eq!(
+        game.to_fen(),
+        "r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23"
+    );
+}
+
+#[test]
+fn comments_nags_and_variations() {
+    let game = read_one(
+        "{Opening comment} 1. e4 $1 {best by test} e5!? (1... c5 {Sicilian} 2. Nf3 (2. c3) d6)
+        ; rest of line comment
+        2. Nf3?! Nc6 $14 *",
+    );
+
+    assert_eq!(game.result, PgnResult::Unknown);
+    assert_eq!(game.moves.len(), 4);
+
+    let e4 = &game.moves[0];
+    assert_eq!(e4.san, "e4");
+    assert_eq!(e4.comments_before, vec!["Opening comment".to_string()]);
+    assert_eq!(e4.nags, vec![1]);
+    assert_eq!(e4.comments, vec!["best by test".to_string()]);
+
+    let e5 = &game.moves[1];
+    assert_eq!(e5.nags, vec![5]);
+    assert_eq!(e5.variations.len(), 1);
+
+    let variation = &e5.variations[0];
+    assert_eq!(variation.len(), 3);
+    assert_eq!(variation[0].san, "c5");
+    assert_eq!(variation[0].comments, vec!["Sicilian".to_string()]);
+    assert_eq!(
+        variation[0].location,
+        Location {
+            line: 1,
+            column: 54
+        }
+    );
+    assert_eq!(
+        variation[1].variations,
+        vec![vec![PgnMove::new(
+            "c3".to_string(),
+            Location {
+                line: 1,
+                column: 79
+            }
+        )]]
+    );
+    assert_eq!(variation[2].san, "d6");
+
+    let nf3 = &game.moves[2];
+    assert_eq!(nf3.nags, vec![6]);
+    assert_eq!(game.moves[3].nags, vec![14]);
+
+    // The line comment follows the variation and belongs to 1... e5
+    assert_eq!(e5.comments, vec!["rest of line comment".to_string()]);
+
+    let game = game.to_game().unwrap();
+    assert_eq!(
+        game.to_fen(),
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+    );
+}
+
+#[test]
+fn line_comment_attaches_to_last_move() {
+    let game = read_one("1. d4 ; queen's pawn\n d5 1/2-1/2");
+    assert_eq!(game.moves[0].comments, vec!["queen's pawn".to_string()]);
+    assert_eq!(game.moves[1].san, "d5");
+    assert_eq!(game.result, PgnResult::Draw);
+}
+
+#[test]
+fn multiple_games() {
+    let input = format!(
+        "{}\n[Event \"Second\"]\n[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n\n% escaped line\n[Event \"Third\"]\n\n1. d4\n",
+        IMMORTAL_GAME
+    );
+
+    let games: Vec<PgnGame> = PgnReader::new(&input).map(|g| g.unwrap()).collect();
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(games[1].result, PgnResult::BlackWins);
+    assert_eq!(games[2].tag("Event"), Some("Third"));
+    assert_eq!(games[2].result, PgnResult::Unknown);
+    assert_eq!(games[2].moves.len(), 1);
+
+    let game = games[1].to_game().unwrap();
+    assert_eq!(game.current_color(), Color::White);
+}
+
+#[test]
+fn escaped_tag_value() {
+    let game = read_one(r#"[Event "The \"big\" one \\ 2020"] *"#);
+    assert_eq!(game.tag("Event"), Some(r#"The "big" one \ 2020"#));
+}
+
+#[test]
+fn fen_tag() {
+    let game =
+        read_one("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 Kd7 2. e5 *");
+    let game = game.to_game().unwrap();
+    assert_eq!(game.to_fen(), "8/3k4/8/4P3/8/8/8/4K3 b - - 0 2");
+
+    let game = read_one("[FEN \"invalid\"]\n\n1. e4 *");
+    assert_eq!(
+        game.to_game().err().unwrap().kind,
+        ParsePgnErrorKind::InvalidFen
+    );
+}
+
+#[test]
+fn syntax_errors() {
+    let error = read_error("[Event \"Unterminated]\n1. e4 *");
+    assert_eq!(error.kind, ParsePgnErrorKind::UnterminatedString);
+    assert_eq!(error.location, Location { line: 1, column: 8 });
+
+    let error = read_error("1. e4 {never closed");
+    assert_eq!(error.kind, ParsePgnErrorKind::UnterminatedComment);
+    assert_eq!(error.location, Location { line: 1, column: 7 });
+
+    let error = read_error("1. e4\n  (1. d4 d5 *");
+    assert_eq!(error.kind, ParsePgnErrorKind::UnexpectedToken);
+    assert_eq!(
+        error.location,
+        Location {
+            line: 2,
+            column: 13
+        }
+    );
+
+    let error = read_error("1. e4\n  (1. d4 d5");
+    assert_eq!(error.kind, ParsePgnErrorKind::UnterminatedVariation);
+    assert_eq!(error.location, Location { line: 2, column: 3 });
+
+    let error = read_error("1. e4 e5)");
+    assert_eq!(error.kind, ParsePgnErrorKind::UnexpectedToken);
+    assert_eq!(error.location, Location { line: 1, column: 9 });
+
+    let error = read_error("1. e4 & e5");
+    assert_eq!(error.kind, ParsePgnErrorKind::UnexpectedChar('&'));
+    assert_eq!(error.location, Location { line: 1, column: 7 });
+
+    let error = read_error("[Event Site]");
+    assert_eq!(error.kind, ParsePgnErrorKind::InvalidTag);
+    assert_eq!(error.location, Location { line: 1, column: 8 });
+
+    let error = read_error("1. e4 $ e5");
+    assert_eq!(error.kind, ParsePgnErrorKind::InvalidNag);
+
+    let error = read_error("$1 1. e4");
+    assert_eq!(error.kind, ParsePgnErrorKind::UnexpectedToken);
+    assert_eq!(error.location, Location { line: 1, column: 1 });
+}
+
+#[test]
+fn illegal_move() {
+    let game = read_one("1. e4 e5\n2. Ke3 *");
+    let error = game.to_game().err().unwrap();
+    assert_eq!(
+        error.kind,
+        ParsePgnErrorKind::IllegalMove("Ke3".to_string(), ParseSanError::NoSuchMove)
+    );
+    assert_eq!(error.location, Location { line: 2, column: 4 });
+}
+
+#[test]
+fn write_round_trip() {
+    let pgn_game = read_one(IMMORTAL_GAME);
+    let game = pgn_game.to_game().unwrap();
+
+    let mut writer = PgnWriter::default();
+    for (name, value) in pgn_game.tags.iter() {
+        writer.set_tag(name, value);
+    }
+
+    let pgn = writer.write(&game);
+    let written = read_one(&pgn);
+
+    assert_eq!(written.tags, pgn_game.tags);
+    assert_eq!(written.result, PgnResult::WhiteWins);
+    assert_eq!(
+        written.moves.iter().map(|m| &m.san).collect::<Vec<_>>(),
+        pgn_game.moves.iter().map(|m| &m.san).collect::<Vec<_>>()
+    );
+    assert!(pgn.lines().all(|line| line.len() <= 80));
+}
+
+#[test]
+fn write_default_tags() {
+    let mut game = Game::default();
+    for san in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7"].iter() {
+        let (from, r#move) = game.parse_san(san).unwrap();
+        game.play(from, r#move);
+    }
+
+    let expected = "[Event \"?\"]
+[Site \"?\"]
+[Date \"????.??.??\"]
+[Round \"?\"]
+[White \"?\"]
+[Black \"?\"]
+[Result \"1-0\"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+";
+
+    assert_eq!(game.to_pgn(), expected);
+}
+
+#[test]
+fn write_custom_tags_and_result() {
+    let mut game = Game::default();
+    let (from, r#move) = game.parse_san("d4").unwrap();
+    game.play(from, r#move);
+
+    let mut writer = PgnWriter::default();
+    writer.set_tag("Event", "The \"big\" match");
+    writer.set_tag("Annotator", "Someone");
+    writer.set_result(PgnResult::Draw);
+
+    let pgn = writer.write(&game);
+    assert!(pgn.contains("[Event \"The \\\"big\\\" match\"]\n"));
+    assert!(pgn.contains("[Result \"1/2-1/2\"]\n[Annotator \"Someone\"]\n"));
+    assert!(pgn.ends_with("\n1. d4 1/2-1/2\n"));
+
+    let read = read_one(&pgn);
+    assert_eq!(read.tag("Event"), Some("The \"big\" match"));
+}
+
+#[test]
+fn write_from_fen() {
+    let fen = "4k3/8/8/8/8/8/7p/R3K3 b Q - 0 30";
+    let mut game = Game::from_fen(fen).unwrap();
+    for san in ["Kd7", "O-O-O"].iter() {
+        let (from, r#move) = game.parse_san(san).unwrap();
+        assert_eq!(game.play(from, r#move), GameResult::Ok);
+    }
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/7p/R3K3 b Q - 0 30\"]\n"));
+    assert!(pgn.ends_with("\n30... Kd7 31. O-O-O+ *\n"));
+
+    let replayed = read_one(&pgn).to_game().unwrap();
+    assert_eq!(replayed.to_fen(), game.to_fen());
+}
+
+#[test]
+fn chess960_variant() {
+    let mut game = Game::from_fen("1k6/8/8/8/8/8/8/R2K2R1 w AG - 0 1").unwrap();
+    for san in ["O-O", "Kc7", "Rf7+"].iter() {
+        let (from, r#move) = game.parse_san(san).unwrap();
+        assert_eq!(game.play(from, r#move), GameResult::Ok);
+    }
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains(
+        "[Variant \"Chess960\"]\n[SetUp \"1\"]\n[FEN \"1k6/8/8/8/8/8/8/R2K2R1 w KQ - 0 1\"]\n"
+    ));
+    assert!(pgn.ends_with("\n1. O-O Kc7 2. Rf7+ *\n"));
+
+    let replayed = read_one(&pgn).to_game().unwrap();
+    assert!(replayed.is_chess960());
+    assert_eq!(replayed.to_fen(), game.to_fen());
+
+    let read = read_one("[Variant \"Chess960\"]\n\n1. e4 *");
+    assert!(read.starting_game().unwrap().is_chess960());
+}
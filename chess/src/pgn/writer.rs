@@ -0,0 +1,132 @@
+use super::{PgnResult, SEVEN_TAG_ROSTER};
+use crate::game::{Game, GameResult, START_FEN};
+use crate::Color;
+
+const MAX_LINE_LENGTH: usize = 80;
+
+pub struct PgnWriter {
+    tags: Vec<(String, String)>,
+    result: Option<PgnResult>,
+}
+
+impl Default for PgnWriter {
+    fn default() -> Self {
+        let tags = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|&tag| {
+                let value = match tag {
+                    "Date" => "????.??.??",
+                    "Result" => "*",
+                    _ => "?",
+                };
+                (tag.to_string(), value.to_string())
+            })
+            .collect();
+
+        PgnWriter { tags, result: None }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl PgnWriter {
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    // Overrides the result, which is otherwise taken from the state of the game
+    pub fn set_result(&mut self, result: PgnResult) {
+        self.result = Some(result);
+    }
+
+    fn game_result(&self, game: &Game) -> PgnResult {
+        if let Some(result) = self.result {
+            return result;
+        }
+
+        match game.status() {
+            GameResult::Checkmate if game.current_color() == Color::White => PgnResult::BlackWins,
+            GameResult::Checkmate => PgnResult::WhiteWins,
+            GameResult::Stalemate | GameResult::Draw(_) => PgnResult::Draw,
+            _ => PgnResult::Unknown,
+        }
+    }
+
+    fn movetext(&self, game: &Game) -> Vec<String> {
+        // Taking every move back gives the start position without parsing its FEN
+        let mut replay = game.clone();
+        while replay.unmake_move().is_some() {}
+        let mut tokens = Vec::new();
+
+        for (i, (from, r#move)) in game.played_moves().enumerate() {
+            let number = replay.fullmove_number();
+            if replay.current_color() == Color::White {
+                tokens.push(format!("{}.", number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", number));
+            }
+
+            match replay.play_san(from, r#move) {
+                Some(san) => tokens.push(san),
+                None => break,
+            }
+        }
+
+        tokens
+    }
+
+    pub fn write(&self, game: &Game) -> String {
+        let result = self.game_result(game);
+
+        let mut pgn = String::new();
+        for (name, value) in self.tags.iter() {
+            let value = if name == "Result" {
+                result.to_string()
+            } else {
+                escape(value)
+            };
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+
+        if game.is_chess960() && !self.tags.iter().any(|(tag, _)| tag == "Variant") {
+            pgn.push_str("[Variant \"Chess960\"]\n");
+        }
+
+        if game.start_fen() != START_FEN && !self.tags.iter().any(|(tag, _)| tag == "FEN") {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", game.start_fen()));
+        }
+        pgn.push('\n');
+
+        let mut tokens = self.movetext(game);
+        tokens.push(result.to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+
+            pgn.push_str(&token);
+            line_length += token.len();
+        }
+        pgn.push('\n');
+
+        pgn
+    }
+}
+
+impl Game {
+    pub fn to_pgn(&self) -> String {
+        PgnWriter::default().write(self)
+    }
+}
@@ -11,7 +11,7 @@ impl FromStr for TaggedPiece {
     type Err = ParseTaggedPieceError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() > 2 || s.len() == 0 {
+        if s.len() > 2 || s.is_empty() {
             return Err(ParseTaggedPieceError::InvalidSize);
         }
 
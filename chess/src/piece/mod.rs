@@ -9,7 +9,7 @@ pub use from_str::*;
 #[cfg(test)]
 mod tests;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 #[repr(u8)]
 pub enum PieceType {
     Pawn = 1,
@@ -32,6 +32,41 @@ impl PieceType {
             _ => None,
         }
     }
+
+    pub fn to_char(self) -> char {
+        match self {
+            PieceType::Pawn => 'P',
+            PieceType::Rook => 'R',
+            PieceType::Knight => 'N',
+            PieceType::Bishop => 'B',
+            PieceType::Queen => 'Q',
+            PieceType::King => 'K',
+        }
+    }
+
+    // Rough worth in centipawns, the king is priced so losing it never pays off
+    pub fn value(self) -> i32 {
+        match self {
+            PieceType::Pawn => 100,
+            PieceType::Knight => 320,
+            PieceType::Bishop => 330,
+            PieceType::Rook => 500,
+            PieceType::Queen => 900,
+            PieceType::King => 20_000,
+        }
+    }
+
+    // Worth with few pieces left, where pawns and rooks gain and minor pieces lose
+    pub fn endgame_value(self) -> i32 {
+        match self {
+            PieceType::Pawn => 120,
+            PieceType::Knight => 290,
+            PieceType::Bishop => 310,
+            PieceType::Rook => 550,
+            PieceType::Queen => 950,
+            PieceType::King => 20_000,
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -45,23 +80,19 @@ impl PartialEq for TaggedPiece {
 
 impl TaggedPiece {
     pub fn empty() -> Self {
-        TaggedPiece { 0: 0 }
+        TaggedPiece(0)
     }
 
     pub fn new(r#type: PieceType, color: Color) -> Self {
-        TaggedPiece {
-            0: (r#type as u8) ^ (color as u8),
-        }
+        TaggedPiece((r#type as u8) ^ (color as u8))
     }
 
     pub fn original(r#type: PieceType, color: Color) -> Self {
-        TaggedPiece {
-            0: ((r#type as u8) ^ (color as u8)) ^ 64,
-        }
+        TaggedPiece(((r#type as u8) ^ (color as u8)) ^ 64)
     }
 
     pub fn non_original(&self) -> Self {
-        TaggedPiece { 0: self.0 & 191 }
+        TaggedPiece(self.0 & 191)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -81,6 +112,6 @@ impl TaggedPiece {
     }
 
     pub fn opposite_color(&self) -> Self {
-        TaggedPiece { 0: self.0 ^ 128 }
+        TaggedPiece(self.0 ^ 128)
     }
 }
@@ -93,3 +93,10 @@ fn from_str() {
     test_non_original("Q", PieceType::Queen);
     test_non_original("K", PieceType::King);
 }
+
+#[test]
+fn to_char() {
+    for c in "PRNBQK".chars() {
+        assert_eq!(PieceType::from_char(c).unwrap().to_char(), c);
+    }
+}
@@ -0,0 +1,7 @@
+use super::Pos;
+
+impl std::fmt::Display for Pos {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", (b'a' + self.x()) as char, self.y() + 1)
+    }
+}
@@ -0,0 +1,33 @@
+use super::Pos;
+
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub enum ParsePosError {
+    InvalidSize,
+    InvalidFile,
+    InvalidRank,
+}
+
+impl FromStr for Pos {
+    type Err = ParsePosError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+        if bytes.len() != 2 {
+            return Err(ParsePosError::InvalidSize);
+        }
+
+        let x = match bytes[0] {
+            b'a'..=b'h' => bytes[0] - b'a',
+            _ => return Err(ParsePosError::InvalidFile),
+        };
+
+        let y = match bytes[1] {
+            b'1'..=b'8' => bytes[1] - b'1',
+            _ => return Err(ParsePosError::InvalidRank),
+        };
+
+        Ok(Pos::new_xy(x, y))
+    }
+}
@@ -1,5 +1,10 @@
 use crate::Color;
 
+pub mod display;
+pub mod from_str;
+
+pub use from_str::*;
+
 #[cfg(test)]
 mod tests;
 
@@ -9,16 +14,16 @@ pub struct Pos(u8);
 impl Pos {
     pub fn new_xy(x: u8, y: u8) -> Self {
         assert!(x < 8 && y < 8);
-        Pos { 0: x | (y << 3) }
+        Pos(x | (y << 3))
     }
 
     pub fn new_index(i: u8) -> Self {
         assert!(i < 64);
-        Self { 0: i }
+        Self(i)
     }
 
     pub const fn invalid() -> Self {
-        Self { 0: std::u8::MAX }
+        Self(u8::MAX)
     }
 
     pub fn index(&self) -> usize {
@@ -44,7 +49,7 @@ impl Pos {
     pub fn add_x(&self, x: u8) -> Option<Self> {
         let new_x = self.x() + x;
         if (0..8).contains(&new_x) {
-            Some(Self { 0: self.0 + x })
+            Some(Self(self.0 + x))
         } else {
             None
         }
@@ -53,14 +58,14 @@ impl Pos {
     pub fn sub_x(&self, x: u8) -> Option<Self> {
         let new_x = self.x() as i8 - x as i8;
         if (0..8).contains(&new_x) {
-            Some(Self { 0: self.0 - x })
+            Some(Self(self.0 - x))
         } else {
             None
         }
     }
 
     pub fn move_x(&self, x: i8) -> Option<Self> {
-        let abs_x = x.abs() as u8;
+        let abs_x = x.unsigned_abs();
         if x > 0 {
             self.add_x(abs_x)
         } else {
@@ -71,9 +76,7 @@ impl Pos {
     pub fn add_y(&self, y: u8) -> Option<Self> {
         let new_y = self.y() + y;
         if (0..8).contains(&new_y) {
-            Some(Self {
-                0: self.0 + (y << 3),
-            })
+            Some(Self(self.0 + (y << 3)))
         } else {
             None
         }
@@ -82,16 +85,14 @@ impl Pos {
     pub fn sub_y(&self, y: u8) -> Option<Self> {
         let new_y = self.y() as i8 - y as i8;
         if (0..8).contains(&new_y) {
-            Some(Self {
-                0: self.0 - (y << 3),
-            })
+            Some(Self(self.0 - (y << 3)))
         } else {
             None
         }
     }
 
     pub fn move_y(&self, y: i8) -> Option<Self> {
-        let abs_y = y.abs() as u8;
+        let abs_y = y.unsigned_abs();
         if y > 0 {
             self.add_y(abs_y)
         } else {
@@ -137,11 +138,11 @@ impl Pos {
     }
 
     pub fn distance_x(&self, other: &Pos) -> u8 {
-        (self.x() as i8 - other.x() as i8).abs() as u8
+        (self.x() as i8 - other.x() as i8).unsigned_abs()
     }
 
     pub fn distance_y(&self, other: &Pos) -> u8 {
-        (self.y() as i8 - other.y() as i8).abs() as u8
+        (self.y() as i8 - other.y() as i8).unsigned_abs()
     }
 }
 
@@ -1,5 +1,7 @@
 use super::*;
 
+use std::str::FromStr;
+
 macro_rules! assert_panic {
     ($expression:expr) => {
         if !std::panic::catch_unwind(|| $expression).is_err() {
@@ -286,8 +288,8 @@ fn distance() {
                     let pos2 = Pos::new_xy(x2, y2);
                     let dist_x = pos.distance_x(&pos2);
                     let dist_y = pos.distance_y(&pos2);
-                    let rdist_x = (x as i8 - x2 as i8).abs() as u8;
-                    let rdist_y = (y as i8 - y2 as i8).abs() as u8;
+                    let rdist_x = (x as i8 - x2 as i8).unsigned_abs();
+                    let rdist_y = (y as i8 - y2 as i8).unsigned_abs();
                     assert_eq!(dist_x, rdist_x);
                     assert_eq!(dist_y, rdist_y);
                 }
@@ -295,3 +297,27 @@ fn distance() {
         }
     }
 }
+
+#[test]
+fn display() {
+    assert_eq!(Pos::new_xy(0, 0).to_string(), "a1");
+    assert_eq!(Pos::new_xy(4, 3).to_string(), "e4");
+    assert_eq!(Pos::new_xy(7, 7).to_string(), "h8");
+}
+
+#[test]
+fn from_str() {
+    for x in 0..8 {
+        for y in 0..8 {
+            let pos = Pos::new_xy(x, y);
+            assert_eq!(Pos::from_str(&pos.to_string()), Ok(pos));
+        }
+    }
+
+    assert_eq!(Pos::from_str(""), Err(ParsePosError::InvalidSize));
+    assert_eq!(Pos::from_str("e44"), Err(ParsePosError::InvalidSize));
+    assert_eq!(Pos::from_str("i4"), Err(ParsePosError::InvalidFile));
+    assert_eq!(Pos::from_str("E4"), Err(ParsePosError::InvalidFile));
+    assert_eq!(Pos::from_str("e9"), Err(ParsePosError::InvalidRank));
+    assert_eq!(Pos::from_str("e0"), Err(ParsePosError::InvalidRank));
+}
@@ -0,0 +1,104 @@
+use crate::engine::Engine;
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+pub mod uci;
+pub mod xboard;
+
+#[cfg(test)]
+mod tests;
+
+pub const ENGINE_NAME: &str = "antlilja-chess";
+pub const ENGINE_AUTHOR: &str = "antlilja";
+
+// Shared with the search thread, which prints its own progress
+type Output<W> = Arc<Mutex<W>>;
+
+fn send<W: Write>(out: &Mutex<W>, line: &str) {
+    let mut out = out.lock().unwrap();
+    // Nothing sensible to do when the other end is gone
+    let _ = writeln!(out, "{}", line);
+    let _ = out.flush();
+}
+
+// Runs searches on a background thread, so commands like stop can be read meanwhile
+struct SearchThread {
+    engine: Option<Engine>,
+    handle: Option<JoinHandle<Engine>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl SearchThread {
+    fn new() -> Self {
+        let engine = Engine::new();
+        Self {
+            stop: engine.stop_flag(),
+            engine: Some(engine),
+            handle: None,
+        }
+    }
+
+    fn is_searching(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    fn start<F>(&mut self, search: F)
+    where
+        F: FnOnce(&mut Engine) + Send + 'static,
+    {
+        let mut engine = self.engine();
+        self.handle = Some(std::thread::spawn(move || {
+            search(&mut engine);
+            engine
+        }));
+    }
+
+    // Blocks until the running search, if any, is done and hands back the engine
+    fn engine(&mut self) -> Engine {
+        self.wait();
+        self.engine.take().unwrap()
+    }
+
+    fn wait(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.engine = Some(handle.join().unwrap());
+        }
+    }
+
+    // A search that hasn't started yet clears the flag when it does, so keep setting it
+    fn stop(&mut self) {
+        if let Some(handle) = &self.handle {
+            while !handle.is_finished() {
+                self.stop.store(true, Ordering::Relaxed);
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+        self.wait();
+    }
+
+    // Borrows the idle engine, stopping any search first
+    fn with_engine<T>(&mut self, f: impl FnOnce(&mut Engine) -> T) -> T {
+        self.stop();
+        f(self.engine.as_mut().unwrap())
+    }
+}
+
+// Share of the remaining clock to spend on the next move
+pub fn time_for_move(
+    remaining: Duration,
+    increment: Duration,
+    moves_to_go: Option<u32>,
+) -> Duration {
+    let moves = moves_to_go.unwrap_or(30).max(1);
+    let budget = remaining / moves + increment * 3 / 4;
+
+    // Keep a little back for the time it takes to send the move
+    let margin = Duration::from_millis(50).min(remaining / 10);
+    budget
+        .min(remaining.saturating_sub(margin))
+        .max(Duration::from_millis(1))
+}
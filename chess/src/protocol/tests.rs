@@ -0,0 +1,334 @@
+use super::uci::Uci;
+use super::xboard::XBoard;
+use super::*;
+
+use std::time::Instant;
+
+// Writer the test keeps a handle to after giving it away
+#[derive(Clone, Default)]
+struct Transcript(Arc<Mutex<Vec<u8>>>);
+
+impl Transcript {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+impl Write for Transcript {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn run_uci(script: &str) -> Vec<String> {
+    let transcript = Transcript::default();
+    Uci::new(transcript.clone()).run(script.as_bytes()).unwrap();
+    transcript.lines()
+}
+
+fn run_xboard(script: &str) -> Vec<String> {
+    let transcript = Transcript::default();
+    XBoard::new(transcript.clone())
+        .run(script.as_bytes())
+        .unwrap();
+    transcript.lines()
+}
+
+fn engine_moves(lines: &[String]) -> Vec<&str> {
+    lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("move "))
+        .collect()
+}
+
+// Polls until the engine has sent count moves, so tests don't race the search thread
+fn wait_for_moves(transcript: &Transcript, count: usize) {
+    let start = Instant::now();
+    while engine_moves(&transcript.lines()).len() < count {
+        assert!(start.elapsed() < Duration::from_secs(30), "no engine move");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn best_move(lines: &[String]) -> &str {
+    let line = lines
+        .iter()
+        .find(|line| line.starts_with("bestmove"))
+        .expect("no bestmove");
+    line.split_whitespace().nth(1).unwrap()
+}
+
+#[test]
+fn uci_handshake() {
+    let lines = run_uci("uci\nisready\nquit\n");
+
+    assert!(lines[0].starts_with("id name"));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("option name Hash")));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("option name Threads")));
+    let uciok = lines.iter().position(|line| line == "uciok").unwrap();
+    assert_eq!(lines[uciok + 1], "readyok");
+}
+
+#[test]
+fn uci_go_depth() {
+    let lines = run_uci("ucinewgame\nposition startpos moves e2e4 e7e5 g1f3\ngo depth 3\n");
+
+    let info: Vec<&String> = lines
+        .iter()
+        .filter(|line| line.starts_with("info depth"))
+        .collect();
+    assert_eq!(info.len(), 3);
+    assert!(info[2].starts_with("info depth 3 score cp "));
+    assert!(info[2].contains(" nodes "));
+    assert!(info[2].contains(" nps "));
+    assert!(info[2].contains(" pv "));
+
+    // Black to move after the three moves
+    let game = {
+        let mut game = crate::game::Game::default();
+        for uci in ["e2e4", "e7e5", "g1f3"].iter() {
+            let (from, r#move) = game.parse_uci(uci).unwrap();
+            game.play(from, r#move);
+        }
+        game
+    };
+    assert!(game.parse_uci(best_move(&lines)).is_ok());
+}
+
+#[test]
+fn uci_position_fen() {
+    let lines = run_uci("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\n");
+
+    assert_eq!(best_move(&lines), "a1a8");
+    assert!(lines.iter().any(|line| line.contains("score mate 1")));
+}
+
+#[test]
+fn uci_castling_and_promotion_moves() {
+    let lines = run_uci(
+        "position fen 4k3/1P6/8/8/8/8/8/4K2R w K - 0 1 moves e1g1 e8d7 b7b8q\nisready\ngo depth 1\n",
+    );
+
+    assert!(!lines.iter().any(|line| line.contains("illegal")));
+    assert!(lines.iter().any(|line| line.starts_with("bestmove")));
+}
+
+#[test]
+fn uci_invalid_input() {
+    let lines = run_uci(
+        "position startpos moves e2e5\nposition fen nonsense\nfoo\nsetoption name Bar value 1\n",
+    );
+
+    assert_eq!(lines[0], "info string illegal move e2e5");
+    assert_eq!(lines[1], "info string invalid position");
+    assert_eq!(lines[2], "info string unknown command foo");
+    assert_eq!(lines[3], "info string unknown option Bar");
+}
+
+#[test]
+fn uci_setoption() {
+    let lines = run_uci("setoption name Hash value 1\nsetoption name Threads value 1\nisready\n");
+    assert_eq!(lines, ["readyok"]);
+}
+
+#[test]
+fn uci_chess960() {
+    let lines = run_uci("uci\n");
+    assert!(lines
+        .iter()
+        .any(|line| line == "option name UCI_Chess960 type check default false"));
+
+    // Castling with the king already on g1 is the only mate
+    let lines = run_uci("position fen 4rkr1/4p1p1/8/8/8/8/8/6KR w H - 0 1\ngo depth 2\n");
+    assert_eq!(best_move(&lines), "g1h1");
+
+    let lines = run_uci(
+        "setoption name UCI_Chess960 value true\nposition startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1h1\nisready\n",
+    );
+    assert_eq!(lines, ["readyok"]);
+}
+
+#[test]
+fn uci_infinite_and_stop() {
+    let transcript = Transcript::default();
+    let mut uci = Uci::new(transcript.clone());
+
+    uci.handle("position startpos");
+    uci.handle("go infinite");
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(uci.is_searching());
+    assert!(!transcript
+        .lines()
+        .iter()
+        .any(|line| line.starts_with("bestmove")));
+
+    uci.handle("stop");
+    assert!(!uci.is_searching());
+    assert!(best_move(&transcript.lines()).len() >= 4);
+}
+
+#[test]
+fn uci_clock() {
+    let start = Instant::now();
+    let lines = run_uci("position startpos\ngo wtime 2000 btime 2000 winc 0 binc 0\n");
+
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(lines.iter().any(|line| line.starts_with("bestmove")));
+}
+
+#[test]
+fn time_management() {
+    let ms = Duration::from_millis;
+
+    assert_eq!(time_for_move(ms(30_000), ms(0), None), ms(1000));
+    assert_eq!(time_for_move(ms(10_000), ms(1000), Some(10)), ms(1750));
+    // Never the whole clock, even on the last move before the time control
+    assert_eq!(time_for_move(ms(1000), ms(0), Some(1)), ms(950));
+    assert_eq!(time_for_move(ms(0), ms(0), None), ms(1));
+}
+
+#[test]
+fn xboard_feature_negotiation() {
+    let lines = run_xboard("xboard\nprotover 2\naccepted usermove\nping 7\n");
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("feature "));
+    assert!(lines[0].contains("usermove=1"));
+    assert!(lines[0].contains("setboard=1"));
+    assert!(lines[0].ends_with("done=1"));
+    assert_eq!(lines[1], "pong 7");
+}
+
+#[test]
+fn xboard_replies_to_user_moves() {
+    let transcript = Transcript::default();
+    let mut xboard = XBoard::new(transcript.clone());
+
+    for line in ["new", "sd 2", "usermove e2e4"].iter() {
+        xboard.handle(line);
+    }
+
+    // The reply is played on the game before the next user move
+    xboard.handle("usermove d2d4");
+    assert_eq!(xboard.game().fullmove_number(), 2);
+    assert_eq!(xboard.game().current_color(), crate::Color::Black);
+
+    // The reply to d2d4 was sent, so force keeps it
+    wait_for_moves(&transcript, 2);
+    xboard.handle("force");
+    assert_eq!(engine_moves(&transcript.lines()).len(), 2);
+    assert_eq!(xboard.game().fullmove_number(), 3);
+    assert_eq!(xboard.game().current_color(), crate::Color::White);
+}
+
+#[test]
+fn xboard_interrupt_keeps_sent_move() {
+    let transcript = Transcript::default();
+    let mut xboard = XBoard::new(transcript.clone());
+
+    for line in ["new", "sd 1", "usermove e2e4"].iter() {
+        xboard.handle(line);
+    }
+
+    wait_for_moves(&transcript, 1);
+    xboard.handle("force");
+    assert_eq!(xboard.game().current_color(), crate::Color::White);
+    assert_eq!(xboard.game().fullmove_number(), 2);
+
+    xboard.handle("usermove g1f3");
+    assert!(!transcript
+        .lines()
+        .iter()
+        .any(|line| line.starts_with("Illegal")));
+
+    // remove takes back g1f3 and the engine's reply
+    xboard.handle("remove");
+    let mut game = crate::game::Game::default();
+    let (from, r#move) = game.parse_uci("e2e4").unwrap();
+    game.play(from, r#move);
+    assert_eq!(xboard.game().to_fen(), game.to_fen());
+}
+
+#[test]
+fn xboard_force_and_go() {
+    let lines = run_xboard("new\nforce\nusermove e2e4\ne7e5\nsd 1\n");
+    assert!(engine_moves(&lines).is_empty());
+
+    // go makes the engine play the side to move, here white
+    let lines = run_xboard("new\nforce\nusermove e2e4\nusermove e7e5\nsd 2\ngo\n");
+    let moves = engine_moves(&lines);
+    assert_eq!(moves.len(), 1);
+
+    let mut game = crate::game::Game::default();
+    for uci in ["e2e4", "e7e5"].iter() {
+        let (from, r#move) = game.parse_uci(uci).unwrap();
+        game.play(from, r#move);
+    }
+    assert!(game.parse_uci(moves[0]).is_ok());
+}
+
+#[test]
+fn xboard_undo_and_remove() {
+    let transcript = Transcript::default();
+    let mut xboard = XBoard::new(transcript.clone());
+    let start = xboard.game().to_fen();
+
+    for line in [
+        "new",
+        "force",
+        "usermove e2e4",
+        "usermove e7e5",
+        "usermove g1f3",
+    ]
+    .iter()
+    {
+        xboard.handle(line);
+    }
+
+    xboard.handle("undo");
+    assert_eq!(xboard.game().current_color(), crate::Color::White);
+    xboard.handle("remove");
+    assert_eq!(xboard.game().to_fen(), start);
+
+    xboard.handle("undo");
+    assert_eq!(transcript.lines(), ["Error (no moves to undo): undo"]);
+}
+
+#[test]
+fn xboard_setboard_and_result() {
+    let lines = run_xboard("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\nsd 3\ngo\n");
+
+    assert_eq!(engine_moves(&lines), ["a1a8"]);
+    assert!(lines.iter().any(|line| line == "1-0 {White mates}"));
+}
+
+#[test]
+fn xboard_invalid_input() {
+    let lines = run_xboard("new\nforce\nusermove e2e5\nsetboard nonsense\nfoo\nsd x\n");
+
+    assert_eq!(lines[0], "Illegal move: e2e5");
+    assert_eq!(lines[1], "tellusererror Illegal position");
+    assert_eq!(lines[2], "Error (unknown command): foo");
+    assert_eq!(lines[3], "Error (bad depth): sd x");
+}
+
+#[test]
+fn xboard_clock() {
+    let start = Instant::now();
+    let lines = run_xboard("new\nlevel 40 0:02 0\ntime 200\notim 200\nusermove e2e4\n");
+
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(engine_moves(&lines).len(), 1);
+}
@@ -0,0 +1,239 @@
+use super::{send, time_for_move, Output, SearchThread, ENGINE_AUTHOR, ENGINE_NAME};
+use crate::engine::{mate_in, Limits, SearchResult, DEFAULT_HASH_MB, MAX_HASH_MB};
+use crate::game::Game;
+use crate::{Color, FullMove};
+
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Universal Chess Interface, one command per line
+pub struct Uci<W> {
+    game: Game,
+    search: SearchThread,
+    out: Output<W>,
+    // UCI_Chess960, castling moves are sent and expected as king takes rook
+    chess960: bool,
+}
+
+fn format_move(game: &Game, r#move: FullMove) -> String {
+    let (from, r#move) = r#move.to_pair();
+    game.to_uci(from, r#move)
+}
+
+fn format_info(game: &Game, result: &SearchResult) -> String {
+    let score = match mate_in(result.score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+
+    let micros = result.time.as_micros().max(1) as u64;
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|&r#move| format_move(game, r#move))
+        .collect();
+
+    format!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        result.nodes * 1_000_000 / micros,
+        result.time.as_millis(),
+        pv.join(" ")
+    )
+}
+
+// Reads the value after a keyword in go, like the 1000 in "wtime 1000"
+fn parse_value<T: std::str::FromStr>(tokens: &[&str], name: &str) -> Option<T> {
+    let i = tokens.iter().position(|&token| token == name)?;
+    tokens.get(i + 1)?.parse().ok()
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            game: Game::default(),
+            search: SearchThread::new(),
+            out: Arc::new(Mutex::new(out)),
+            chess960: false,
+        }
+    }
+
+    // Handles commands until quit or the end of the input, then waits for the last search
+    pub fn run<R: BufRead>(&mut self, input: R) -> std::io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?) {
+                return Ok(());
+            }
+        }
+
+        self.search.wait();
+        Ok(())
+    }
+
+    // Returns false once the engine should quit
+    pub fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens.first().copied() {
+            Some("uci") => self.uci(),
+            Some("isready") => self.send("readyok"),
+            Some("ucinewgame") => {
+                self.search.with_engine(|engine| engine.clear_hash());
+                self.game = Game::default();
+            }
+            Some("position") => self.position(&tokens[1..]),
+            Some("go") => self.go(&tokens[1..]),
+            Some("stop") => self.search.stop(),
+            Some("setoption") => self.set_option(&tokens[1..]),
+            Some("quit") => {
+                self.search.stop();
+                return false;
+            }
+            Some(command) => self.send(&format!("info string unknown command {}", command)),
+            None => {}
+        }
+
+        true
+    }
+
+    fn send(&self, line: &str) {
+        send(&self.out, line);
+    }
+
+    fn uci(&self) {
+        self.send(&format!("id name {}", ENGINE_NAME));
+        self.send(&format!("id author {}", ENGINE_AUTHOR));
+        self.send(&format!(
+            "option name Hash type spin default {} min 1 max {}",
+            DEFAULT_HASH_MB, MAX_HASH_MB
+        ));
+        // The search is single threaded, the option is there for GUIs that always set it
+        self.send("option name Threads type spin default 1 min 1 max 1");
+        self.send("option name UCI_Chess960 type check default false");
+        self.send("uciok");
+    }
+
+    // position startpos|fen <fen> [moves <move>...]
+    fn position(&mut self, tokens: &[&str]) {
+        self.search.stop();
+
+        let moves_at = tokens
+            .iter()
+            .position(|&token| token == "moves")
+            .unwrap_or(tokens.len());
+
+        let game = match tokens.first().copied() {
+            Some("startpos") => Ok(Game::default()),
+            Some("fen") => Game::from_fen(&tokens[1..moves_at].join(" ")).map_err(|_| ()),
+            _ => Err(()),
+        };
+
+        let mut game = match game {
+            Ok(game) => game,
+            Err(_) => return self.send("info string invalid position"),
+        };
+        if self.chess960 {
+            game.set_chess960(true);
+        }
+
+        for uci in tokens.iter().skip(moves_at + 1) {
+            match game.parse_uci(uci) {
+                Ok((from, r#move)) => {
+                    game.play(from, r#move);
+                }
+                Err(_) => return self.send(&format!("info string illegal move {}", uci)),
+            }
+        }
+
+        self.game = game;
+    }
+
+    fn limits(&self, tokens: &[&str]) -> Limits {
+        let millis = |name| parse_value::<u64>(tokens, name).map(Duration::from_millis);
+
+        let mut limits = Limits {
+            depth: parse_value(tokens, "depth"),
+            nodes: parse_value(tokens, "nodes"),
+            time: millis("movetime"),
+        };
+
+        if tokens.contains(&"infinite") {
+            return limits;
+        }
+
+        let (time, increment) = if self.game.current_color() == Color::White {
+            ("wtime", "winc")
+        } else {
+            ("btime", "binc")
+        };
+
+        if let Some(remaining) = millis(time) {
+            let increment = millis(increment).unwrap_or_default();
+            let moves_to_go = parse_value(tokens, "movestogo");
+            limits.time = Some(time_for_move(remaining, increment, moves_to_go));
+        }
+
+        limits
+    }
+
+    fn go(&mut self, tokens: &[&str]) {
+        self.search.stop();
+
+        let limits = self.limits(tokens);
+        let game = self.game.clone();
+        let out = self.out.clone();
+
+        self.search.start(move |engine| {
+            let result = engine.search_with(&game, limits, |result| {
+                send(&out, &format_info(&game, result));
+            });
+
+            let best_move = match result.best_move {
+                Some(r#move) => format_move(&game, r#move),
+                None => "0000".to_string(),
+            };
+
+            match result.pv.get(1) {
+                Some(&ponder) => send(
+                    &out,
+                    &format!(
+                        "bestmove {} ponder {}",
+                        best_move,
+                        format_move(&game, ponder)
+                    ),
+                ),
+                None => send(&out, &format!("bestmove {}", best_move)),
+            }
+        });
+    }
+
+    // setoption name <name> [value <value>]
+    fn set_option(&mut self, tokens: &[&str]) {
+        let value_at = tokens
+            .iter()
+            .position(|&token| token == "value")
+            .unwrap_or(tokens.len());
+        let name = tokens.get(1..value_at).unwrap_or(&[]).join(" ");
+        let value = tokens.get(value_at + 1..).unwrap_or(&[]).join(" ");
+
+        match (name.to_ascii_lowercase().as_str(), value.parse::<usize>()) {
+            ("hash", Ok(megabytes)) => {
+                let megabytes = megabytes.clamp(1, MAX_HASH_MB);
+                self.search
+                    .with_engine(|engine| engine.set_hash_size(megabytes));
+            }
+            ("threads", Ok(_)) => {}
+            ("uci_chess960", _) if value == "true" || value == "false" => {
+                self.chess960 = value == "true";
+            }
+            _ => self.send(&format!("info string unknown option {}", name)),
+        }
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search.is_searching()
+    }
+}
@@ -0,0 +1,340 @@
+use super::{send, time_for_move, Output, SearchThread, ENGINE_NAME};
+use crate::engine::{Limits, SearchResult};
+use crate::game::{DrawReason, Game, GameResult};
+use crate::{Color, FullMove};
+
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Chess Engine Communication Protocol, as spoken by xboard and WinBoard
+pub struct XBoard<W> {
+    game: Game,
+    search: SearchThread,
+    out: Output<W>,
+    // Move the search thread sent but the game hasn't played yet
+    pending: Arc<Mutex<Option<FullMove>>>,
+    // Set when a search is abandoned, so it ends without sending its move
+    cancel: Arc<AtomicBool>,
+    force: bool,
+    engine_color: Color,
+    post: bool,
+    depth: Option<u32>,
+    move_time: Option<Duration>,
+    // level <moves per control> <base> <increment>, 0 moves means the whole game
+    moves_per_control: u32,
+    increment: Duration,
+    clock: Option<Duration>,
+}
+
+fn format_move(game: &Game, r#move: FullMove) -> String {
+    let (from, r#move) = r#move.to_pair();
+    game.to_uci(from, r#move)
+}
+
+// Line sent when the game is over, None while it goes on
+fn result_line(game: &Game, status: GameResult) -> Option<String> {
+    let line = match status {
+        GameResult::Checkmate if game.current_color() == Color::Black => "1-0 {White mates}",
+        GameResult::Checkmate => "0-1 {Black mates}",
+        GameResult::Stalemate => "1/2-1/2 {Stalemate}",
+        GameResult::Draw(DrawReason::InsufficientMaterial) => "1/2-1/2 {Insufficient material}",
+        GameResult::Draw(DrawReason::ThreefoldRepetition)
+        | GameResult::Draw(DrawReason::FivefoldRepetition) => "1/2-1/2 {Draw by repetition}",
+        GameResult::Draw(DrawReason::FiftyMoveRule)
+        | GameResult::Draw(DrawReason::SeventyFiveMoveRule) => "1/2-1/2 {Draw by move rule}",
+        GameResult::Ok | GameResult::InvalidMove => return None,
+    };
+
+    Some(line.to_string())
+}
+
+// Thinking output: ply, score, time in centiseconds, nodes and the line
+fn format_thinking(game: &Game, result: &SearchResult) -> String {
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|&r#move| format_move(game, r#move))
+        .collect();
+
+    format!(
+        "{} {} {} {} {}",
+        result.depth,
+        result.score,
+        result.time.as_millis() / 10,
+        result.nodes,
+        pv.join(" ")
+    )
+}
+
+// Minutes or minutes:seconds
+fn parse_base_time(base: &str) -> Option<Duration> {
+    let mut parts = base.splitn(2, ':');
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: u64 = match parts.next() {
+        Some(seconds) => seconds.parse().ok()?,
+        None => 0,
+    };
+
+    Some(Duration::from_secs(minutes * 60 + seconds))
+}
+
+impl<W: Write + Send + 'static> XBoard<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            game: Game::default(),
+            search: SearchThread::new(),
+            out: Arc::new(Mutex::new(out)),
+            pending: Arc::new(Mutex::new(None)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            force: false,
+            engine_color: Color::Black,
+            post: false,
+            depth: None,
+            move_time: None,
+            moves_per_control: 0,
+            increment: Duration::from_secs(0),
+            clock: None,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // Handles commands until quit or the end of the input, then waits for the last search
+    pub fn run<R: BufRead>(&mut self, input: R) -> std::io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?) {
+                return Ok(());
+            }
+        }
+
+        self.sync();
+        Ok(())
+    }
+
+    // Returns false once the engine should quit
+    pub fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let argument = |i: usize| tokens.get(i).copied().unwrap_or("");
+
+        match tokens.first().copied() {
+            Some("xboard") | Some("accepted") | Some("rejected") | Some("hard") | Some("easy")
+            | Some("random") | Some("computer") => {}
+            Some("protover") => self.send(&format!(
+                "feature myname=\"{}\" ping=1 setboard=1 usermove=1 time=1 draw=0 sigint=0 \
+                 sigterm=0 reuse=1 analyze=0 colors=0 done=1",
+                ENGINE_NAME
+            )),
+            Some("new") => {
+                self.interrupt();
+                self.search.with_engine(|engine| engine.clear_hash());
+                self.game = Game::default();
+                self.force = false;
+                self.engine_color = Color::Black;
+                self.depth = None;
+            }
+            Some("force") => {
+                self.interrupt();
+                self.force = true;
+            }
+            Some("go") => {
+                self.sync();
+                self.force = false;
+                self.engine_color = self.game.current_color();
+                self.think();
+            }
+            Some("usermove") => self.user_move(argument(1)),
+            Some("?") => {
+                self.search.stop();
+                self.sync();
+            }
+            Some("undo") => self.take_back(1),
+            Some("remove") => self.take_back(2),
+            Some("setboard") => {
+                self.interrupt();
+                match Game::from_fen(&tokens[1..].join(" ")) {
+                    Ok(game) => self.game = game,
+                    Err(_) => self.send("tellusererror Illegal position"),
+                }
+            }
+            Some("level") => {
+                let level = (
+                    argument(1).parse(),
+                    parse_base_time(argument(2)),
+                    argument(3).parse::<f64>(),
+                );
+
+                match level {
+                    (Ok(moves), Some(base), Ok(increment)) => {
+                        self.moves_per_control = moves;
+                        self.clock = Some(base);
+                        self.increment = Duration::from_secs_f64(increment);
+                        self.move_time = None;
+                    }
+                    _ => self.error("bad level", line),
+                }
+            }
+            Some("st") => match argument(1).parse() {
+                Ok(seconds) => self.move_time = Some(Duration::from_secs(seconds)),
+                Err(_) => self.error("bad time", line),
+            },
+            Some("sd") => match argument(1).parse() {
+                Ok(depth) => self.depth = Some(depth),
+                Err(_) => self.error("bad depth", line),
+            },
+            Some("time") => match argument(1).parse::<u64>() {
+                Ok(centiseconds) => self.clock = Some(Duration::from_millis(centiseconds * 10)),
+                Err(_) => self.error("bad time", line),
+            },
+            // The opponent's clock doesn't change how long we think
+            Some("otim") => {}
+            Some("ping") => self.send(&format!("pong {}", argument(1))),
+            Some("post") => self.post = true,
+            Some("nopost") => self.post = false,
+            Some("result") => {
+                self.interrupt();
+                self.force = true;
+            }
+            Some("quit") => {
+                self.interrupt();
+                return false;
+            }
+            // Moves without usermove, for interfaces that ignore the feature
+            Some(command) if self.sync_and_parse(command) => self.user_move(command),
+            Some(command) => self.error("unknown command", command),
+            None => {}
+        }
+
+        true
+    }
+
+    fn send(&self, line: &str) {
+        send(&self.out, line);
+    }
+
+    fn error(&self, kind: &str, command: &str) {
+        self.send(&format!("Error ({}): {}", kind, command));
+    }
+
+    // Waits for a running search and plays the move it came up with
+    fn sync(&mut self) {
+        self.search.wait();
+        self.play_pending();
+    }
+
+    // Ends a running search. A move it already sent is played, since the
+    // interface has seen it, otherwise the search ends without sending one.
+    fn interrupt(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.search.stop();
+        self.cancel.store(false, Ordering::Relaxed);
+        self.play_pending();
+    }
+
+    fn play_pending(&mut self) {
+        if let Some(r#move) = self.pending.lock().unwrap().take() {
+            let (from, r#move) = r#move.to_pair();
+            self.game.play(from, r#move);
+        }
+    }
+
+    fn sync_and_parse(&mut self, command: &str) -> bool {
+        self.sync();
+        self.game.parse_uci(command).is_ok()
+    }
+
+    fn take_back(&mut self, count: usize) {
+        self.interrupt();
+        if !self.game.undo(count) {
+            self.error(
+                "no moves to undo",
+                if count == 1 { "undo" } else { "remove" },
+            );
+        }
+    }
+
+    fn user_move(&mut self, text: &str) {
+        self.sync();
+
+        let (from, r#move) = match self.game.parse_uci(text) {
+            Ok(r#move) => r#move,
+            Err(_) => return self.send(&format!("Illegal move: {}", text)),
+        };
+
+        let status = self.game.play(from, r#move);
+        if let Some(line) = result_line(&self.game, status) {
+            return self.send(&line);
+        }
+
+        if !self.force && self.game.current_color() == self.engine_color {
+            self.think();
+        }
+    }
+
+    fn limits(&self) -> Limits {
+        let time = match (self.move_time, self.clock) {
+            (Some(time), _) => Some(time),
+            (None, Some(remaining)) => {
+                let moves_to_go = if self.moves_per_control > 0 {
+                    let played = self.game.fullmove_number() - 1;
+                    Some(self.moves_per_control - played % self.moves_per_control)
+                } else {
+                    None
+                };
+
+                Some(time_for_move(remaining, self.increment, moves_to_go))
+            }
+            // Nothing set at all, don't think forever
+            (None, None) => Some(Duration::from_secs(5)),
+        };
+
+        Limits {
+            depth: self.depth,
+            nodes: None,
+            time,
+        }
+    }
+
+    fn think(&mut self) {
+        if self.game.legal_moves().next().is_none() {
+            return;
+        }
+
+        let limits = self.limits();
+        let game = self.game.clone();
+        let out = self.out.clone();
+        let pending = self.pending.clone();
+        let cancel = self.cancel.clone();
+        let post = self.post;
+
+        self.search.start(move |engine| {
+            let result = engine.search_with(&game, limits, |result| {
+                if post {
+                    send(&out, &format_thinking(&game, result));
+                }
+            });
+
+            // Cancelling and publishing the move can't interleave, the move is
+            // pending exactly when it was sent
+            let mut pending = pending.lock().unwrap();
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let best_move = result.best_move.unwrap();
+            *pending = Some(best_move);
+            send(&out, &format!("move {}", format_move(&game, best_move)));
+            drop(pending);
+
+            let mut game = game;
+            let (from, r#move) = best_move.to_pair();
+            let status = game.play(from, r#move);
+            if let Some(line) = result_line(&game, status) {
+                send(&out, &line);
+            }
+        });
+    }
+}
@@ -0,0 +1,246 @@
+use super::index::{Layout, Material, Placement};
+use super::{Outcome, Table, Tablebase, DRAW, INVALID};
+use crate::bitboard::Bitboard;
+use crate::bitboard::{bishop_attacks, king_attacks, knight_attacks, queen_attacks, rook_attacks};
+use crate::game::Game;
+use crate::{Color, PieceType, Pos};
+
+// Retrograde analysis. A first pass looks at every position with the regular
+// legal move generation: it finds the mates and stalemates, settles moves into
+// smaller tables and counts the moves that stay in this one. After that,
+// positions are settled in order of plies to mate. Once a position is known,
+// the positions one move before it are found by moving pieces backwards: if it
+// was lost they are won, if it was won they lose once every move does.
+struct Generator<'a> {
+    tablebase: &'a Tablebase,
+    material: Material,
+    layout: Layout,
+    values: Vec<u8>,
+    // Moves within the table not yet known to lead to a win for the opponent
+    remaining: Vec<u8>,
+    // Longest loss through a capture or promotion, 0 if there is none
+    exit_loss: Vec<u8>,
+    // A capture or promotion that doesn't lose, so the position can't be lost
+    escapes: Vec<bool>,
+    // Positions to settle with the index as plies to mate, unless a shorter
+    // mate was found for them first
+    buckets: Vec<Vec<u32>>,
+}
+
+pub(super) fn generate(tablebase: &Tablebase, material: Material) -> Table {
+    let layout = Layout::new(&material);
+    let size = layout.size();
+    let mut generator = Generator {
+        tablebase,
+        material,
+        layout,
+        values: vec![DRAW; size],
+        remaining: vec![0; size],
+        exit_loss: vec![0; size],
+        escapes: vec![false; size],
+        buckets: Vec::new(),
+    };
+
+    for index in 0..size {
+        generator.analyze(index);
+    }
+
+    let mut plies = 0;
+    while plies < generator.buckets.len() {
+        for index in std::mem::take(&mut generator.buckets[plies]) {
+            let index = index as usize;
+            if generator.values[index] == DRAW {
+                assert!(plies + 1 < INVALID as usize, "mate too long to store");
+                generator.values[index] = (plies + 1) as u8;
+                generator.retract(index, plies);
+            }
+        }
+        plies += 1;
+    }
+
+    Table {
+        material: generator.material,
+        layout: generator.layout,
+        values: generator.values,
+    }
+}
+
+// Squares a pawn could have come from without capturing
+fn pawn_origins(to: Pos, color: Color, occupied: Bitboard) -> Bitboard {
+    let (back, start): (i8, u8) = if color == Color::White {
+        (-1, 1)
+    } else {
+        (1, 6)
+    };
+    let mut origins = Bitboard::EMPTY;
+
+    let one = to.y() as i8 + back;
+    if !(1..=6).contains(&one) {
+        return origins;
+    }
+
+    let one = Pos::new_xy(to.x(), one as u8);
+    if occupied.contains(one) {
+        return origins;
+    }
+    origins.insert(one);
+
+    let two = Pos::new_xy(to.x(), start);
+    if (one.y() as i8 + back) as u8 == start && !occupied.contains(two) {
+        origins.insert(two);
+    }
+
+    origins
+}
+
+impl<'a> Generator<'a> {
+    fn push(&mut self, plies: usize, index: usize) {
+        if self.buckets.len() <= plies {
+            self.buckets.resize(plies + 1, Vec::new());
+        }
+        self.buckets[plies].push(index as u32);
+    }
+
+    // Legal positions have one piece per square, no pawns on the first or last
+    // rank and the side that just moved out of check
+    fn is_legal(&self, placement: &Placement) -> bool {
+        let squares = &placement.squares[..self.layout.count];
+        for (i, pos) in squares.iter().enumerate() {
+            if squares[..i].contains(pos) {
+                return false;
+            }
+
+            if self.layout.pieces[i].0 == PieceType::Pawn && (pos.y() == 0 || pos.y() == 7) {
+                return false;
+            }
+        }
+
+        // Mirrored copies are stored under one index only
+        if placement.canonical(&self.layout) != *placement {
+            return false;
+        }
+
+        let board = placement.to_board(&self.layout);
+        let other = placement.player.flip();
+        !board.pos_in_danger(board.find_king(other), other)
+    }
+
+    fn analyze(&mut self, index: usize) {
+        let placement = Placement::from_index(&self.layout, index);
+        if !self.is_legal(&placement) {
+            self.values[index] = INVALID;
+            return;
+        }
+
+        let board = placement.to_board(&self.layout);
+        let player = placement.player;
+        let game = Game::from_position(board, player);
+
+        let mut successors = Vec::new();
+        let mut has_moves = false;
+        for r#move in game.legal_moves() {
+            has_moves = true;
+
+            // Quiet moves only move one piece, there are no castling rights
+            if !game.is_capture(&r#move) && r#move.promotion().is_none() {
+                let mut next = placement;
+                next.player = player.flip();
+                for square in next.squares[..self.layout.count].iter_mut() {
+                    if *square == r#move.from {
+                        *square = r#move.to;
+                    }
+                }
+                successors.push(next.canonical(&self.layout).index(&self.layout));
+                continue;
+            }
+
+            let after = board.after_move(r#move.from, r#move.r#move, player);
+            let outcome = self
+                .tablebase
+                .probe_board(&after, player.flip())
+                .expect("smaller tables are generated first");
+
+            match outcome.before_move() {
+                Outcome::Win(plies) => {
+                    self.escapes[index] = true;
+                    self.push(plies as usize, index);
+                }
+                Outcome::Draw => self.escapes[index] = true,
+                Outcome::Loss(plies) => {
+                    self.exit_loss[index] = self.exit_loss[index].max(plies as u8);
+                }
+            }
+        }
+
+        successors.sort_unstable();
+        successors.dedup();
+        self.remaining[index] = successors.len() as u8;
+
+        if !has_moves {
+            if game.is_king_in_danger() {
+                self.push(0, index);
+            }
+        } else if successors.is_empty() && !self.escapes[index] {
+            self.push(self.exit_loss[index] as usize, index);
+        }
+    }
+
+    // Updates the positions one move before a newly settled one
+    fn retract(&mut self, index: usize, plies: usize) {
+        let placement = Placement::from_index(&self.layout, index);
+        let board = placement.to_board(&self.layout);
+        let occupied = board.occupied();
+        let mover = placement.player.flip();
+
+        let mut predecessors = Vec::new();
+        for i in 0..self.layout.count {
+            let (r#type, color) = self.layout.pieces[i];
+            if color != mover {
+                continue;
+            }
+
+            let to = placement.squares[i];
+            let origins = match r#type {
+                PieceType::King => king_attacks(to),
+                PieceType::Knight => knight_attacks(to),
+                PieceType::Bishop => bishop_attacks(to, occupied),
+                PieceType::Rook => rook_attacks(to, occupied),
+                PieceType::Queen => queen_attacks(to, occupied),
+                PieceType::Pawn => pawn_origins(to, color, occupied),
+            } & !occupied;
+
+            for from in origins {
+                let mut squares = placement.squares;
+                squares[i] = from;
+
+                let previous = Placement {
+                    squares,
+                    player: mover,
+                }
+                .canonical(&self.layout)
+                .index(&self.layout);
+
+                // Illegal positions are marked invalid, settled ones need no update
+                if self.values[previous] == DRAW {
+                    predecessors.push(previous);
+                }
+            }
+        }
+
+        // Symmetric positions can be reached more than one way, count each once
+        predecessors.sort_unstable();
+        predecessors.dedup();
+
+        for previous in predecessors {
+            if plies & 1 == 0 {
+                self.push(plies + 1, previous);
+            } else {
+                self.remaining[previous] -= 1;
+                if self.remaining[previous] == 0 && !self.escapes[previous] {
+                    let loss = (plies + 1).max(self.exit_loss[previous] as usize);
+                    self.push(loss, previous);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use super::TablebaseError;
+use crate::{Board, Color, PieceType, Pos, TaggedPiece};
+
+pub const MAX_PIECES: usize = 4;
+
+// Pieces besides the two kings, strongest first on each side
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Material {
+    white: Vec<PieceType>,
+    black: Vec<PieceType>,
+}
+
+fn sort_pieces(pieces: &mut [PieceType]) {
+    pieces.sort_by_key(|r#type| -r#type.value());
+}
+
+impl Material {
+    pub fn new(mut white: Vec<PieceType>, mut black: Vec<PieceType>) -> Self {
+        sort_pieces(&mut white);
+        sort_pieces(&mut black);
+        Self { white, black }
+    }
+
+    pub fn from_board(board: &Board) -> Self {
+        let side = |color| {
+            board
+                .occupied_by(color)
+                .map(|pos| board.at_pos(pos).get_type())
+                .filter(|&r#type| r#type != PieceType::King)
+                .collect()
+        };
+
+        Self::new(side(Color::White), side(Color::Black))
+    }
+
+    pub fn piece_count(&self) -> usize {
+        2 + self.white.len() + self.black.len()
+    }
+
+    pub fn has_pawns(&self) -> bool {
+        self.white
+            .iter()
+            .chain(self.black.iter())
+            .any(|&r#type| r#type == PieceType::Pawn)
+    }
+
+    // Neither side has mating material, so every position is a draw
+    pub fn is_dead(&self) -> bool {
+        let minor =
+            |r#type: &PieceType| *r#type == PieceType::Knight || *r#type == PieceType::Bishop;
+        self.white.len() + self.black.len() <= 1
+            && self.white.iter().chain(self.black.iter()).all(minor)
+    }
+
+    // Tables are only kept with the stronger side as white, the other way
+    // around is probed with the board mirrored and the colors swapped
+    pub fn is_canonical(&self) -> bool {
+        let strength = |pieces: &[PieceType]| {
+            let values: Vec<i32> = pieces.iter().map(|r#type| r#type.value()).collect();
+            (pieces.len(), values)
+        };
+
+        strength(&self.white) >= strength(&self.black)
+    }
+
+    pub fn flipped(&self) -> Self {
+        Self {
+            white: self.black.clone(),
+            black: self.white.clone(),
+        }
+    }
+
+    pub fn canonical(&self) -> Self {
+        if self.is_canonical() {
+            self.clone()
+        } else {
+            self.flipped()
+        }
+    }
+
+    // White king, black king, then the rest in the order squares are indexed by
+    pub fn pieces(&self) -> Vec<(PieceType, Color)> {
+        let mut pieces = vec![
+            (PieceType::King, Color::White),
+            (PieceType::King, Color::Black),
+        ];
+        pieces.extend(self.white.iter().map(|&r#type| (r#type, Color::White)));
+        pieces.extend(self.black.iter().map(|&r#type| (r#type, Color::Black)));
+        pieces
+    }
+
+    // Materials reachable by one capture or promotion
+    pub fn successors(&self) -> Vec<Material> {
+        let mut materials = Vec::new();
+
+        for (side, other, color) in [
+            (&self.white, &self.black, Color::White),
+            (&self.black, &self.white, Color::Black),
+        ]
+        .iter()
+        {
+            for i in 0..side.len() {
+                let mut rest = side.to_vec();
+                let removed = rest.remove(i);
+
+                let mut add = |pieces: Vec<PieceType>| {
+                    let material = if *color == Color::White {
+                        Material::new(pieces, other.to_vec())
+                    } else {
+                        Material::new(other.to_vec(), pieces)
+                    };
+                    materials.push(material.canonical());
+                };
+
+                add(rest.clone());
+
+                if removed == PieceType::Pawn {
+                    for &r#type in [
+                        PieceType::Queen,
+                        PieceType::Rook,
+                        PieceType::Bishop,
+                        PieceType::Knight,
+                    ]
+                    .iter()
+                    {
+                        let mut promoted = rest.clone();
+                        promoted.push(r#type);
+                        add(promoted);
+                    }
+                }
+            }
+        }
+
+        materials.sort_by_key(|material| material.to_string());
+        materials.dedup();
+        materials
+    }
+
+    // Positions in the table, most of them only there to keep indexing simple
+    pub fn size(&self) -> usize {
+        Layout::new(self).size()
+    }
+}
+
+impl std::str::FromStr for Material {
+    type Err = TablebaseError;
+
+    // Like KRKP, the white king and pieces then the black king and pieces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sides = s
+            .strip_prefix('K')
+            .ok_or(TablebaseError::InvalidMaterial)?
+            .split('K');
+        let mut parse = || -> Result<Vec<PieceType>, TablebaseError> {
+            sides
+                .next()
+                .ok_or(TablebaseError::InvalidMaterial)?
+                .chars()
+                .map(|c| PieceType::from_char(c).ok_or(TablebaseError::InvalidMaterial))
+                .collect()
+        };
+
+        let white = parse()?;
+        let black = parse()?;
+        if sides.next().is_some() {
+            return Err(TablebaseError::InvalidMaterial);
+        }
+
+        let material = Material::new(white, black);
+        if material.piece_count() > MAX_PIECES {
+            return Err(TablebaseError::TooManyPieces);
+        }
+
+        Ok(material)
+    }
+}
+
+impl std::fmt::Display for Material {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side: fn(&[PieceType]) -> String =
+            |pieces| pieces.iter().map(|r#type| r#type.to_char()).collect();
+        write!(f, "K{}K{}", side(&self.white), side(&self.black))
+    }
+}
+
+// Squares the white king is kept on: the a1-d1-d4 triangle without pawns,
+// since the board can be mirrored and rotated, otherwise the queen side
+fn anchor_squares(pawns: bool) -> usize {
+    if pawns {
+        32
+    } else {
+        10
+    }
+}
+
+fn anchor_slot(pos: Pos, pawns: bool) -> Option<usize> {
+    let (x, y) = (pos.x() as usize, pos.y() as usize);
+    if x > 3 {
+        return None;
+    }
+
+    if pawns {
+        Some(y * 4 + x)
+    } else if y <= x {
+        // Row by row: a1 b1 c1 d1, b2 c2 d2, c3 d3, d4
+        Some([0, 4, 7, 9][y] + x - y)
+    } else {
+        None
+    }
+}
+
+fn anchor_pos(slot: usize, pawns: bool) -> Pos {
+    if pawns {
+        return Pos::new_xy((slot % 4) as u8, (slot / 4) as u8);
+    }
+
+    let y = [0, 4, 7, 9]
+        .iter()
+        .rposition(|&start| start <= slot)
+        .unwrap();
+    Pos::new_xy((slot - [0, 4, 7, 9][y] + y) as u8, y as u8)
+}
+
+// Bit 0 mirrors files, bit 1 ranks and bit 2 the a1-h8 diagonal
+pub(super) fn transform(pos: Pos, symmetry: u8) -> Pos {
+    let (mut x, mut y) = (pos.x(), pos.y());
+    if symmetry & 1 != 0 {
+        x = 7 - x;
+    }
+    if symmetry & 2 != 0 {
+        y = 7 - y;
+    }
+    if symmetry & 4 != 0 {
+        std::mem::swap(&mut x, &mut y);
+    }
+    Pos::new_xy(x, y)
+}
+
+// Material spelled out piece by piece in index order, cheap to copy around
+#[derive(Copy, Clone)]
+pub struct Layout {
+    pub pieces: [(PieceType, Color); MAX_PIECES],
+    pub count: usize,
+    pub pawns: bool,
+}
+
+impl Layout {
+    pub fn new(material: &Material) -> Self {
+        let mut pieces = [(PieceType::King, Color::White); MAX_PIECES];
+        let list = material.pieces();
+        pieces[..list.len()].copy_from_slice(&list);
+
+        Self {
+            pieces,
+            count: list.len(),
+            pawns: material.has_pawns(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        anchor_squares(self.pawns) * 64usize.pow(self.count as u32 - 1) * 2
+    }
+}
+
+// A position as one square per piece of its material, plus the side to move
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Placement {
+    pub squares: [Pos; MAX_PIECES],
+    pub player: Color,
+}
+
+impl Placement {
+    pub fn from_board(layout: &Layout, board: &Board, player: Color) -> Self {
+        let mut squares = [Pos::new_index(0); MAX_PIECES];
+        let mut taken = crate::bitboard::Bitboard::EMPTY;
+
+        for (i, square) in squares[..layout.count].iter_mut().enumerate() {
+            let (r#type, color) = layout.pieces[i];
+            if i == 0 || layout.pieces[i - 1] != layout.pieces[i] {
+                taken = board.pieces_of(r#type, color);
+            }
+
+            let pos = taken.first().unwrap();
+            taken.remove(pos);
+            *square = pos;
+        }
+
+        Self { squares, player }
+    }
+
+    pub fn to_board(self, layout: &Layout) -> Board {
+        let mut board = Board::empty();
+        for (&pos, &(r#type, color)) in self
+            .squares
+            .iter()
+            .zip(layout.pieces[..layout.count].iter())
+        {
+            board.set_pos(pos, TaggedPiece::new(r#type, color));
+        }
+        board
+    }
+
+    // The same position mirrored and rotated so it has exactly one index: white
+    // king in the anchor squares, identical pieces in square order and, of the
+    // symmetries that allows, the one with the smallest squares
+    pub fn canonical(&self, layout: &Layout) -> Self {
+        let symmetries = if layout.pawns { 2 } else { 8 };
+        let count = layout.count;
+
+        let mut best: Option<[u8; MAX_PIECES]> = None;
+        for symmetry in 0..symmetries {
+            if anchor_slot(transform(self.squares[0], symmetry), layout.pawns).is_none() {
+                continue;
+            }
+
+            let mut squares = [0; MAX_PIECES];
+            for (square, &pos) in squares[..count].iter_mut().zip(self.squares.iter()) {
+                *square = transform(pos, symmetry).index() as u8;
+            }
+
+            // At most two of a kind with four pieces
+            for i in 1..count {
+                if layout.pieces[i] == layout.pieces[i - 1] && squares[i] < squares[i - 1] {
+                    squares.swap(i, i - 1);
+                }
+            }
+
+            if best.map_or(true, |best| squares < best) {
+                best = Some(squares);
+            }
+        }
+
+        let best = best.unwrap();
+        let mut squares = self.squares;
+        for (square, &index) in squares[..count].iter_mut().zip(best.iter()) {
+            *square = Pos::new_index(index);
+        }
+
+        Self {
+            squares,
+            player: self.player,
+        }
+    }
+
+    // Only meaningful for canonical placements
+    pub fn index(&self, layout: &Layout) -> usize {
+        let mut index = anchor_slot(self.squares[0], layout.pawns).unwrap();
+        for pos in self.squares[1..layout.count].iter() {
+            index = index * 64 + pos.index();
+        }
+
+        index * 2 + if self.player == Color::White { 0 } else { 1 }
+    }
+
+    pub fn from_index(layout: &Layout, mut index: usize) -> Self {
+        let player = if index & 1 == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+        index /= 2;
+
+        let mut squares = [Pos::new_index(0); MAX_PIECES];
+        for i in (1..layout.count).rev() {
+            squares[i] = Pos::new_index((index % 64) as u8);
+            index /= 64;
+        }
+        squares[0] = anchor_pos(index, layout.pawns);
+
+        Self { squares, player }
+    }
+}
+
+// The board seen from the other side, with the colors swapped
+pub fn flip_colors(board: &Board) -> Board {
+    let mut flipped = Board::empty();
+    for pos in board.occupied() {
+        let piece = board.at_pos(pos);
+        flipped.set_pos(
+            Pos::new_xy(pos.x(), 7 - pos.y()),
+            TaggedPiece::new(piece.get_type(), piece.color().flip()),
+        );
+    }
+    flipped
+}
@@ -0,0 +1,331 @@
+use crate::game::Game;
+use crate::{Board, Color, Move, Pos};
+
+use std::collections::HashMap;
+use std::path::Path;
+
+mod generate;
+mod index;
+
+pub use index::Material;
+use index::{flip_colors, Layout, Placement};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: &[u8; 4] = b"ACTB";
+
+// Value codes, anything in between is the plies to mate plus one
+const DRAW: u8 = 0;
+const INVALID: u8 = 255;
+
+#[derive(Debug)]
+pub enum TablebaseError {
+    Io(std::io::Error),
+    InvalidMaterial,
+    TooManyPieces,
+    InvalidFile,
+}
+
+impl From<std::io::Error> for TablebaseError {
+    fn from(error: std::io::Error) -> Self {
+        TablebaseError::Io(error)
+    }
+}
+
+impl std::fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TablebaseError::Io(error) => write!(f, "{}", error),
+            TablebaseError::InvalidMaterial => write!(f, "invalid material"),
+            TablebaseError::TooManyPieces => {
+                write!(f, "at most {} pieces are supported", index::MAX_PIECES)
+            }
+            TablebaseError::InvalidFile => write!(f, "invalid tablebase file"),
+        }
+    }
+}
+
+impl std::error::Error for TablebaseError {}
+
+// Result with perfect play from the side to move's view, with the number of
+// plies until mate. Loss(0) is being checkmated.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win(u32),
+    Draw,
+    Loss(u32),
+}
+
+impl Outcome {
+    fn from_value(value: u8) -> Option<Self> {
+        match value {
+            INVALID => None,
+            DRAW => Some(Outcome::Draw),
+            value => {
+                let plies = value as u32 - 1;
+                if plies % 2 == 1 {
+                    Some(Outcome::Win(plies))
+                } else {
+                    Some(Outcome::Loss(plies))
+                }
+            }
+        }
+    }
+
+    // The same result one ply earlier, for the side that moved into it
+    fn before_move(self) -> Self {
+        match self {
+            Outcome::Win(plies) => Outcome::Loss(plies + 1),
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss(plies) => Outcome::Win(plies + 1),
+        }
+    }
+
+    // Higher is better, quick wins first and slow losses last
+    fn rank(self) -> i64 {
+        match self {
+            Outcome::Win(plies) => i64::MAX - plies as i64,
+            Outcome::Draw => 0,
+            Outcome::Loss(plies) => i64::MIN + plies as i64,
+        }
+    }
+}
+
+// Win, draw or loss and distance to mate for every position of one material
+// set, one byte per position after mirroring and rotating
+pub struct Table {
+    material: Material,
+    layout: Layout,
+    values: Vec<u8>,
+}
+
+impl Table {
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn probe(&self, placement: &Placement) -> Option<Outcome> {
+        let index = placement.canonical(&self.layout).index(&self.layout);
+        Outcome::from_value(self.values[index])
+    }
+
+    // Magic, material name and then the run length encoded values
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name = self.material.to_string();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+        compress(&self.values, &mut bytes);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TablebaseError> {
+        if bytes.len() < 5 || &bytes[0..4] != MAGIC {
+            return Err(TablebaseError::InvalidFile);
+        }
+
+        let name_end = 5 + bytes[4] as usize;
+        let name = bytes
+            .get(5..name_end)
+            .and_then(|name| std::str::from_utf8(name).ok())
+            .ok_or(TablebaseError::InvalidFile)?;
+        let material: Material = name.parse()?;
+
+        let layout = Layout::new(&material);
+        if !material.is_canonical() {
+            return Err(TablebaseError::InvalidFile);
+        }
+
+        let values =
+            decompress(&bytes[name_end..], layout.size()).ok_or(TablebaseError::InvalidFile)?;
+
+        Ok(Self {
+            material,
+            layout,
+            values,
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TablebaseError> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TablebaseError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+// Draws and invalid positions come in long runs, they are stored as the value
+// followed by the run length as a LEB128 varint. Wins and losses are stored as is.
+fn compress(values: &[u8], bytes: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < values.len() {
+        let value = values[i];
+        bytes.push(value);
+        i += 1;
+
+        if value != DRAW && value != INVALID {
+            continue;
+        }
+
+        let start = i - 1;
+        while i < values.len() && values[i] == value {
+            i += 1;
+        }
+
+        let mut run = i - start;
+        while run >= 0x80 {
+            bytes.push(run as u8 | 0x80);
+            run >>= 7;
+        }
+        bytes.push(run as u8);
+    }
+}
+
+// None unless the bytes expand to exactly size values
+fn decompress(bytes: &[u8], size: usize) -> Option<Vec<u8>> {
+    let mut values = Vec::with_capacity(size);
+    let mut bytes = bytes.iter().copied();
+
+    while let Some(value) = bytes.next() {
+        if value != DRAW && value != INVALID {
+            values.push(value);
+            continue;
+        }
+
+        let mut run = 0_usize;
+        let mut shift = 0;
+        loop {
+            let byte = bytes.next()?;
+            run |= ((byte & 0x7F) as usize).checked_shl(shift)?;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        if run == 0 || values.len() + run > size {
+            return None;
+        }
+        values.resize(values.len() + run, value);
+    }
+
+    if values.len() == size {
+        Some(values)
+    } else {
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct Tablebase {
+    tables: HashMap<Material, Table>,
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Generates the table for a material set like KRKP, along with the ones it
+    // can turn into through captures and promotions. Dead draws like KBK get none.
+    pub fn generate(&mut self, material: &str) -> Result<(), TablebaseError> {
+        self.generate_material(&material.parse::<Material>()?.canonical());
+        Ok(())
+    }
+
+    fn generate_material(&mut self, material: &Material) {
+        if material.is_dead() || self.tables.contains_key(material) {
+            return;
+        }
+
+        for successor in material.successors() {
+            self.generate_material(&successor);
+        }
+
+        let table = generate::generate(self, material.clone());
+        self.tables.insert(material.clone(), table);
+    }
+
+    pub fn insert(&mut self, table: Table) {
+        self.tables.insert(table.material.clone(), table);
+    }
+
+    pub fn table(&self, material: &Material) -> Option<&Table> {
+        self.tables.get(&material.canonical())
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    // None for positions without a table or with castling rights
+    pub fn probe(&self, game: &Game) -> Option<Outcome> {
+        let board = game.board();
+        if board.has_castling_rights(Color::White) || board.has_castling_rights(Color::Black) {
+            return None;
+        }
+
+        self.probe_game(game)
+    }
+
+    // The tables don't know about en passant, those positions are resolved
+    // through their moves. Taking en passant leaves none for the other side.
+    fn probe_game(&self, game: &Game) -> Option<Outcome> {
+        if game
+            .legal_moves()
+            .any(|r#move| matches!(r#move.r#move, Move::EnPassant(_)))
+        {
+            return self
+                .move_outcomes(game)?
+                .into_iter()
+                .map(|(_, _, outcome)| outcome)
+                .max_by_key(|outcome| outcome.rank());
+        }
+
+        self.probe_board(game.board(), game.current_color())
+    }
+
+    // Result of every legal move for the side to move, None if one is unknown
+    fn move_outcomes(&self, game: &Game) -> Option<Vec<(Pos, Move, Outcome)>> {
+        let mut next = game.clone();
+        game.legal_moves()
+            .map(|r#move| {
+                next.make_move(r#move.from, r#move.r#move);
+                let outcome = self.probe_game(&next);
+                next.unmake_move();
+                Some((r#move.from, r#move.r#move, outcome?.before_move()))
+            })
+            .collect()
+    }
+
+    fn probe_board(&self, board: &Board, player: Color) -> Option<Outcome> {
+        if board.has_insufficient_material() {
+            return Some(Outcome::Draw);
+        }
+
+        let material = Material::from_board(board);
+        if material.piece_count() > index::MAX_PIECES {
+            return None;
+        }
+
+        if !material.is_canonical() {
+            return self.probe_board(&flip_colors(board), player.flip());
+        }
+
+        let table = self.tables.get(&material)?;
+        table.probe(&Placement::from_board(&table.layout, board, player))
+    }
+
+    // Move keeping the best result, winning as fast and losing as slowly as possible
+    pub fn best_move(&self, game: &Game) -> Option<(Pos, Move, Outcome)> {
+        self.probe(game)?;
+
+        self.move_outcomes(game)?
+            .into_iter()
+            .max_by_key(|&(_, _, outcome)| outcome.rank())
+    }
+}
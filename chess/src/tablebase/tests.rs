@@ -0,0 +1,299 @@
+use super::*;
+
+fn probe(tablebase: &Tablebase, fen: &str) -> Option<Outcome> {
+    tablebase.probe(&Game::from_fen(fen).unwrap())
+}
+
+// Plays the tablebase's moves for both sides and returns how long the game took
+fn play_out(tablebase: &Tablebase, fen: &str) -> u32 {
+    let mut game = Game::from_fen(fen).unwrap();
+    let mut plies = 0;
+
+    while let Some((from, r#move, _)) = tablebase.best_move(&game) {
+        game.play(from, r#move);
+        plies += 1;
+    }
+
+    assert!(game.legal_moves().next().is_none());
+    assert!(game.is_king_in_danger());
+    plies
+}
+
+fn longest_win(table: &Table) -> u32 {
+    table
+        .values
+        .iter()
+        .filter_map(|&value| match Outcome::from_value(value) {
+            Some(Outcome::Win(plies)) => Some(plies),
+            _ => None,
+        })
+        .max()
+        .unwrap()
+}
+
+#[test]
+fn material() {
+    let material: Material = "KRKP".parse().unwrap();
+    assert_eq!(material.to_string(), "KRKP");
+    assert_eq!(material.piece_count(), 4);
+    assert!(material.has_pawns());
+
+    assert_eq!(
+        "KKP".parse::<Material>().unwrap().canonical().to_string(),
+        "KPK"
+    );
+    assert_eq!(
+        "KNKB".parse::<Material>().unwrap().canonical().to_string(),
+        "KBKN"
+    );
+    assert!("KBK".parse::<Material>().unwrap().is_dead());
+    assert!(!"KNNK".parse::<Material>().unwrap().is_dead());
+
+    let successors: Vec<String> = material
+        .successors()
+        .iter()
+        .map(|material| material.to_string())
+        .collect();
+    assert_eq!(successors, ["KPK", "KQKR", "KRK", "KRKB", "KRKN", "KRKR"]);
+
+    assert!(matches!(
+        "KQ".parse::<Material>(),
+        Err(TablebaseError::InvalidMaterial)
+    ));
+    assert!(matches!(
+        "KQRKP".parse::<Material>(),
+        Err(TablebaseError::TooManyPieces)
+    ));
+}
+
+#[test]
+fn indexing() {
+    let layout = Layout::new(&"KBNK".parse().unwrap());
+
+    let placement = Placement {
+        squares: [
+            Pos::new_xy(6, 5),
+            Pos::new_xy(0, 7),
+            Pos::new_xy(3, 3),
+            Pos::new_xy(4, 2),
+        ],
+        player: Color::Black,
+    };
+    let canonical = placement.canonical(&layout);
+    let index = canonical.index(&layout);
+    assert!(index < layout.size());
+    assert_eq!(Placement::from_index(&layout, index), canonical);
+
+    // Every mirror image of a position shares its index
+    for symmetry in 0..8 {
+        let mirrored = Placement {
+            squares: [0, 1, 2, 3].map(|i| index::transform(placement.squares[i], symmetry)),
+            player: Color::Black,
+        };
+        assert_eq!(mirrored.canonical(&layout).index(&layout), index);
+    }
+}
+
+#[test]
+fn queen_and_rook_mates() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KQK").unwrap();
+    tablebase.generate("KRK").unwrap();
+
+    assert_eq!(
+        probe(&tablebase, "7k/8/6K1/8/8/8/8/1Q6 w - - 0 1"),
+        Some(Outcome::Win(1))
+    );
+    assert_eq!(
+        probe(&tablebase, "7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"),
+        Some(Outcome::Loss(0))
+    );
+    assert_eq!(
+        probe(&tablebase, "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+        Some(Outcome::Draw)
+    );
+    // Black takes the queen
+    assert_eq!(
+        probe(&tablebase, "8/8/8/8/8/8/1kQ5/7K b - - 0 1"),
+        Some(Outcome::Draw)
+    );
+    // The colors swapped
+    assert_eq!(
+        probe(&tablebase, "7K/8/6k1/8/8/8/8/1q6 b - - 0 1"),
+        Some(Outcome::Win(1))
+    );
+
+    // Known longest mates, 10 and 16 moves
+    let kqk = tablebase.table(&"KQK".parse().unwrap()).unwrap();
+    let krk = tablebase.table(&"KRK".parse().unwrap()).unwrap();
+    assert_eq!(longest_win(kqk), 19);
+    assert_eq!(longest_win(krk), 31);
+
+    let fen = "8/8/3k4/8/8/8/8/R3K3 w - - 0 1";
+    let outcome = probe(&tablebase, fen).unwrap();
+    assert_eq!(outcome, Outcome::Win(play_out(&tablebase, fen)));
+}
+
+#[test]
+fn pawn_endings() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KPK").unwrap();
+
+    // Generated along the way for the promotions
+    assert!(tablebase.table(&"KQK".parse().unwrap()).is_some());
+    assert!(tablebase.table(&"KBK".parse().unwrap()).is_none());
+
+    assert!(matches!(
+        probe(&tablebase, "k7/4P3/8/8/8/8/8/7K w - - 0 1"),
+        Some(Outcome::Win(_))
+    ));
+    // The pawn falls
+    assert_eq!(
+        probe(&tablebase, "8/8/8/8/8/8/3kP3/7K b - - 0 1"),
+        Some(Outcome::Draw)
+    );
+    // King on the sixth in front of its pawn wins whoever moves, the black
+    // king in front of the pawn draws
+    assert!(matches!(
+        probe(&tablebase, "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"),
+        Some(Outcome::Loss(_))
+    ));
+    assert_eq!(
+        probe(&tablebase, "8/8/8/8/4k3/8/4P3/4K3 w - - 0 1"),
+        Some(Outcome::Draw)
+    );
+
+    // Black pawn, same table
+    assert_eq!(
+        probe(&tablebase, "7k/8/8/8/8/8/4p3/K7 b - - 0 1"),
+        probe(&tablebase, "k7/4P3/8/8/8/8/8/7K w - - 0 1")
+    );
+
+    let fen = "8/8/8/8/8/k7/6P1/6K1 w - - 0 1";
+    let outcome = probe(&tablebase, fen).unwrap();
+    assert_eq!(outcome, Outcome::Win(play_out(&tablebase, fen)));
+}
+
+#[test]
+fn en_passant_after_double_push() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KPK").unwrap();
+
+    // A made up table, where only the position after e4 is lost for black
+    // if en passant is forgotten
+    let material: Material = "KPKP".parse().unwrap();
+    let layout = Layout::new(&material);
+    let mut values = vec![DRAW; layout.size()];
+    let mut game = Game::from_fen("7k/8/8/8/3p4/8/4P3/K7 w - - 0 1").unwrap();
+    game.play(Pos::new_xy(4, 1), Move::Move(Pos::new_xy(4, 3)));
+    let placement = Placement::from_board(&layout, game.board(), Color::Black);
+    values[placement.canonical(&layout).index(&layout)] = 1;
+    tablebase.insert(Table {
+        material,
+        layout,
+        values,
+    });
+
+    // Black takes en passant and the pawn runs
+    assert!(matches!(tablebase.probe(&game), Some(Outcome::Win(_))));
+    game.undo(1);
+    let (_, r#move, outcome) = tablebase.best_move(&game).unwrap();
+    assert_ne!(r#move, Move::Move(Pos::new_xy(4, 3)));
+    assert_eq!(outcome, Outcome::Draw);
+}
+
+#[test]
+fn probe_limits() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KBK").unwrap();
+    assert_eq!(tablebase.tables().count(), 0);
+
+    assert_eq!(
+        probe(&tablebase, "8/8/8/4k3/8/8/8/B3K3 w - - 0 1"),
+        Some(Outcome::Draw)
+    );
+    assert_eq!(probe(&tablebase, "8/8/8/4k3/8/8/8/Q3K3 w - - 0 1"), None);
+    assert_eq!(probe(&tablebase, "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1"), None);
+    assert_eq!(Tablebase::new().probe(&Game::default()), None);
+}
+
+#[test]
+fn save_and_load() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KQK").unwrap();
+    let table = tablebase.table(&"KQK".parse().unwrap()).unwrap();
+
+    // Draws and invalid positions are run length encoded
+    let bytes = table.to_bytes();
+    assert!(bytes.len() < table.values.len());
+    let loaded = Table::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded.material(), table.material());
+    assert_eq!(loaded.values, table.values);
+
+    let mut reloaded = Tablebase::new();
+    reloaded.insert(loaded);
+    assert_eq!(
+        probe(&reloaded, "7k/8/6K1/8/8/8/8/1Q6 w - - 0 1"),
+        Some(Outcome::Win(1))
+    );
+
+    assert!(matches!(
+        Table::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(TablebaseError::InvalidFile)
+    ));
+    let mut truncated_run = bytes.clone();
+    truncated_run.push(DRAW);
+    assert!(matches!(
+        Table::from_bytes(&truncated_run),
+        Err(TablebaseError::InvalidFile)
+    ));
+    assert!(matches!(
+        Table::from_bytes(b"nope"),
+        Err(TablebaseError::InvalidFile)
+    ));
+}
+
+#[test]
+fn bishop_and_knight_mate() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KBNK").unwrap();
+
+    // Known longest mate, 33 moves
+    let kbnk = tablebase.table(&"KBNK".parse().unwrap()).unwrap();
+    assert_eq!(longest_win(kbnk), 65);
+
+    // Black takes the knight
+    assert_eq!(
+        probe(&tablebase, "8/8/8/8/8/8/1kN5/4KB2 b - - 0 1"),
+        Some(Outcome::Draw)
+    );
+
+    let fen = "8/8/8/3k4/8/8/8/4KBN1 w - - 0 1";
+    let outcome = probe(&tablebase, fen).unwrap();
+    assert_eq!(outcome, Outcome::Win(play_out(&tablebase, fen)));
+}
+
+#[test]
+fn rook_against_pawn() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KRKP").unwrap();
+
+    // After Ka2 b1=Q+ Rxb1 the king guards the rook, from h8 it is too far
+    assert!(matches!(
+        probe(&tablebase, "8/8/8/8/8/8/1pk5/K6R w - - 0 1"),
+        Some(Outcome::Win(_))
+    ));
+    assert_eq!(
+        probe(&tablebase, "7K/8/8/8/8/8/1pk5/7R w - - 0 1"),
+        Some(Outcome::Draw)
+    );
+    // Promoting with check, the queen then skewers the rook
+    assert!(matches!(
+        probe(&tablebase, "8/8/8/8/8/2k5/1p6/4K2R b - - 0 1"),
+        Some(Outcome::Win(_))
+    ));
+
+    let fen = "8/8/8/8/k7/8/p7/K6R w - - 0 1";
+    let outcome = probe(&tablebase, fen).unwrap();
+    assert_eq!(outcome, Outcome::Win(play_out(&tablebase, fen)));
+}
@@ -4,7 +4,7 @@ use chess::game::{Game, GameResult};
 use chess::{Color, Move, PieceType, Pos};
 use piston_window::{Button, GenericEvent, MouseButton};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 #[derive(PartialEq)]
 pub enum Ending {
@@ -13,12 +13,12 @@ pub enum Ending {
     Tie,
 }
 
-impl ToString for Ending {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Ending {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Ending::White => "White Wins!".to_string(),
-            Ending::Black => "Black Wins!".to_string(),
-            Ending::Tie => "It's a Tie!".to_string(),
+            Ending::White => write!(f, "White Wins!"),
+            Ending::Black => write!(f, "Black Wins!"),
+            Ending::Tie => write!(f, "It's a Tie!"),
         }
     }
 }
@@ -92,18 +92,15 @@ impl GameController {
         if let Some(handler) = &mut self.connection_handler {
             match message {
                 Message::Decline => {
-                    if let Some(previous) = handler.last_sent {
-                    } else {
+                    if handler.last_sent.is_none() {
                         handler.write_message(Message::Decline)?;
                     }
                 }
                 Message::Move(move_type) => {
                     if allow_play {
-                        let (origin, r#move) = move_type.to_chess_move();
-                        let pos = match origin {
-                            Some(pos) => pos,
-                            None => self.game.king_pos,
-                        };
+                        let (pos, r#move) = move_type
+                            .to_full_move(&self.game)
+                            .to_pair();
 
                         self.selected_square = Some([pos.x() as usize, pos.y() as usize]);
                         self.execute_move(r#move);
@@ -133,7 +130,7 @@ impl GameController {
     fn handle_local_result(&mut self, origin: Pos, r#move: Move, turn_result: GameResult) {
         if let Some(handler) = &mut self.connection_handler {
             if turn_result != GameResult::InvalidMove {
-                handler.write_message(Message::from_chess_move(origin, r#move).unwrap());
+                let _ = handler.write_message(Message::from_chess_move(origin, r#move).unwrap());
             }
         } else {
             panic!("Couldn't fetch connection handler");
@@ -145,7 +142,7 @@ impl GameController {
             match turn_result {
                 GameResult::Ok => Ok(()),
                 GameResult::Checkmate => handler.write_message(Message::Checkmate),
-                GameResult::Stalemate => handler.write_message(Message::Draw),
+                GameResult::Stalemate | GameResult::Draw(_) => handler.write_message(Message::Draw),
                 GameResult::InvalidMove => handler.write_message(Message::Decline),
             }.unwrap()
         } else {
@@ -238,7 +235,7 @@ impl GameController {
                     };
                     self.state = State::End(color);
                 }
-                GameResult::Stalemate => self.state = State::End(Ending::Tie),
+                GameResult::Stalemate | GameResult::Draw(_) => self.state = State::End(Ending::Tie),
                 GameResult::InvalidMove => {
                     panic!("Move was in current move but game returned InvalidMove")
                 }
@@ -260,35 +257,14 @@ impl GameController {
     fn get_current_moves(&mut self) {
         if let Some(position) = self.selected_square {
             let current_pos = Pos::new_xy(position[0] as u8, position[1] as u8);
-            self.current_moves = match self.game.moves_for_pos(current_pos) {
-                Some(move_slice) => {
-                    let mut moves = HashMap::new();
-                    for r#move in move_slice.iter() {
-                        match r#move {
-                            Move::Move(pos) => {
-                                moves.insert([pos.x() as usize, pos.y() as usize], *r#move);
-                            }
-                            Move::EnPassant(pos) => {
-                                moves.insert([pos.x() as usize, pos.y() as usize], *r#move);
-                            }
-                            Move::PawnPromotion(_, pos) => {
-                                moves
-                                    .entry([pos.x() as usize, pos.y() as usize])
-                                    .or_insert(*r#move);
-                            }
-                            Move::KingSideCastling => {
-                                moves.insert([position[0] + 2, position[1]], *r#move);
-                            }
-                            Move::QueenSideCastling => {
-                                moves.insert([position[0] - 2, position[1]], *r#move);
-                            }
-                            _ => (),
-                        }
-                    }
-                    Some(moves)
-                }
-                None => None,
-            };
+            let mut moves = HashMap::new();
+            for legal in self.game.moves_from(current_pos) {
+                // All promotions share a square, the piece is chosen afterwards
+                moves
+                    .entry([legal.to.x() as usize, legal.to.y() as usize])
+                    .or_insert(legal.r#move);
+            }
+            self.current_moves = Some(moves);
         }
     }
 }
@@ -3,8 +3,7 @@ use gui::game_controller::GameController;
 use gui::network::ConnectionHandler;
 use gui::view::{View, ViewSettings};
 use piston_window::*;
-use std::io::{self, Read};
-use std::net::IpAddr;
+use std::io;
 
 fn get_input() -> String {
     let mut input = String::new();
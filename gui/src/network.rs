@@ -1,4 +1,5 @@
-use chess::{Move, PieceType, Pos};
+use chess::game::Game;
+use chess::{FullMove, Move, PieceType, Pos};
 use std::collections::VecDeque;
 use std::io::prelude::*;
 use std::net::{IpAddr, TcpListener, TcpStream};
@@ -38,6 +39,15 @@ impl MoveType {
         }
     }
 
+    // The protocol leaves out the king square when castling, the mover's king is
+    // looked up in the game, which may be a Chess960 one
+    pub fn to_full_move(&self, game: &Game) -> FullMove {
+        match self.to_chess_move() {
+            (Some(origin), r#move) => FullMove::from_pair(origin, r#move),
+            (None, r#move) => FullMove::from_pair(game.king_pos, r#move),
+        }
+    }
+
     pub fn to_chess_move(&self) -> (Option<Pos>, Move) {
         match self {
             MoveType::Standard(origin, target) => {
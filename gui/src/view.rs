@@ -84,7 +84,7 @@ impl View {
                 );
                 let texture: piston_window::G2dTexture = Texture::from_path(
                     &mut window.create_texture_context(),
-                    &Path::new(&format!("gui/resources/pieces/{}-{}.png", color, piece)),
+                    Path::new(&format!("gui/resources/pieces/{}-{}.png", color, piece)),
                     piston_window::Flip::None,
                     &TextureSettings::new(),
                 )
@@ -102,11 +102,10 @@ impl View {
         clear(self.settings.background_color, g);
 
         // Draw board
-        self.draw_board(&controller, &c, g);
+        self.draw_board(controller, &c, g);
 
-        match &controller.state {
-            State::Promotion(_) => self.draw_promotion_choice(&controller, &c, g),
-            _ => (),
+        if let State::Promotion(_) = &controller.state {
+            self.draw_promotion_choice(controller, &c, g);
         }
     }
 
@@ -228,7 +227,7 @@ impl View {
                 let image = Image::new().rect([x_pos, y_pos, cell_size, cell_size]);
 
                 image.draw(
-                    &mut self.textures[index as usize],
+                    &self.textures[index as usize],
                     &piston_window::DrawState::default(),
                     c.transform,
                     g,